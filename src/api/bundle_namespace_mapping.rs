@@ -9,6 +9,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::api::comparable::ResourceDiff;
 
+/// Well-known label with the namespace name, selecting the mapped namespace.
+pub static NAMESPACE_NAME_LABEL: &str = "kubernetes.io/metadata.name";
+
 mod mapping {
     use kube::CustomResource;
     use schemars::JsonSchema;
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap, sync::OnceLock};
 
 use cluster_api_rs::capi_cluster::{ClusterSpec, ClusterStatus};
 use fleet_api_rs::{
@@ -7,21 +7,27 @@ use fleet_api_rs::{
     },
     fleet_clustergroup::{ClusterGroupSelector, ClusterGroupSpec},
 };
-use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::{
+    NamespaceResourceScope,
+    api::core::v1::{Namespace, ObjectReference},
+};
 use kube::{
-    CustomResource, Resource, ResourceExt as _,
-    api::{ObjectMeta, TypeMeta},
+    Client, Resource, ResourceExt as _,
+    api::{DynamicObject, ObjectMeta, TypeMeta},
 };
 #[cfg(feature = "agent-initiated")]
 use rand::distr::{Alphanumeric, SampleString as _};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 
 use super::{
-    bundle_namespace_mapping::BundleNamespaceMapping,
-    fleet_addon_config::ClusterConfig,
+    bundle_namespace_mapping::{BundleNamespaceMapping, NAMESPACE_NAME_LABEL},
+    fleet_addon_config::{ClusterConfig, meta_placeholder},
     fleet_cluster,
-    fleet_clustergroup::{CLUSTER_CLASS_LABEL, CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup},
+    fleet_clustergroup::{
+        CLUSTER_CLASS_LABEL, CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup, GROUP_RULE_LABEL,
+    },
 };
 
 #[cfg(feature = "agent-initiated")]
@@ -30,21 +36,244 @@ use super::fleet_cluster_registration_token::ClusterRegistrationToken;
 pub static FLEET_WORKSPACE_ANNOTATION: &str =
     "field.cattle.io/allow-fleetworkspace-creation-for-existing-namespace";
 
+/// API group of the CAPI `Cluster` and `ClusterClass` resources.
+pub static CLUSTER_API_GROUP: &str = "cluster.x-k8s.io";
+
+/// Version of the CAPI API selected during startup discovery.
+static SERVED_VERSION: OnceLock<ClusterApiVersion> = OnceLock::new();
+
+/// `ClusterApiVersion` is the version of the `cluster.x-k8s.io` API used for
+/// `Cluster` and `ClusterClass` requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClusterApiVersion {
+    #[default]
+    V1beta1,
+    V1beta2,
+}
+
+impl ClusterApiVersion {
+    /// Discovers the preferred version of the CAPI API served by the management cluster.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the API group can't be discovered.
+    pub async fn discover(client: &Client) -> kube::Result<Self> {
+        let group = kube::discovery::oneshot::group(client, CLUSTER_API_GROUP).await?;
+        Ok(match group.preferred_version_or_latest() {
+            "v1beta2" => Self::V1beta2,
+            _ => Self::V1beta1,
+        })
+    }
+
+    /// Selects the version for all `Cluster` and `ClusterClass` requests.
+    /// Only the first selection takes effect.
+    pub fn serve(self) {
+        let _ = SERVED_VERSION.set(self);
+    }
+
+    /// Returns the selected version, `v1beta1` unless another version was selected.
+    #[must_use]
+    pub fn served() -> Self {
+        SERVED_VERSION.get().copied().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1beta1 => "v1beta1",
+            Self::V1beta2 => "v1beta2",
+        }
+    }
+
+    /// Adjusts a condition to the format of the served version.
+    /// `v1beta2` uses `metav1.Condition`, which has no severity and requires a message.
+    pub(crate) fn to_condition(self, mut condition: Value) -> Value {
+        if self == Self::V1beta2 {
+            if let Some(condition) = condition.as_object_mut() {
+                condition.remove("severity");
+                condition.entry("message").or_insert_with(|| json!(""));
+            }
+        }
+        condition
+    }
+
+    fn api_version(self) -> String {
+        format!("{CLUSTER_API_GROUP}/{}", self.as_str())
+    }
+
+    /// Checks if the object is served in this version.
+    pub(crate) fn matches(self, value: &Value) -> bool {
+        value["apiVersion"] == self.api_version()
+    }
+}
+
+/// Splits an API version into the group and version.
+/// References converted from `v1beta2` contain only the API group, and have no version.
+pub(crate) fn split_api_version(api_version: &str) -> (&str, Option<&str>) {
+    match api_version.split_once('/') {
+        Some((group, version)) => (group, Some(version)),
+        None if api_version.contains('.') => (api_version, None),
+        None => ("", Some(api_version)),
+    }
+}
+
 /// `ClusterProxy` defines the desired state of the CAPI Cluster.
-#[derive(CustomResource, Serialize, Deserialize, Clone, Debug, JsonSchema)]
-#[kube(
-    group = "cluster.x-k8s.io",
-    version = "v1beta1",
-    kind = "Cluster",
-    plural = "clusters"
-)]
-#[kube(namespaced)]
-#[kube(status = "ClusterStatus")]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct ClusterProxy {
     #[serde(flatten)]
     pub proxy: ClusterSpec,
 }
 
+/// CAPI `Cluster` in the `v1beta1` layout. Requests use the served API version,
+/// and `v1beta2` objects are converted to the `v1beta1` layout on read.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "Value")]
+pub struct Cluster {
+    #[serde(flatten)]
+    pub types: Option<TypeMeta>,
+    pub metadata: ObjectMeta,
+    pub spec: ClusterProxy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ClusterStatus>,
+}
+
+#[derive(Deserialize)]
+struct ClusterFields {
+    #[serde(default)]
+    metadata: ObjectMeta,
+    spec: ClusterProxy,
+    #[serde(default)]
+    status: Option<ClusterStatus>,
+}
+
+impl TryFrom<Value> for Cluster {
+    type Error = serde_json::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let value = if ClusterApiVersion::V1beta2.matches(&value) {
+            from_v1beta2(value)
+        } else {
+            value
+        };
+
+        let ClusterFields {
+            metadata,
+            spec,
+            status,
+        } = serde_json::from_value(value)?;
+        Ok(Self {
+            types: Some(TypeMeta::resource::<Self>()),
+            metadata,
+            spec,
+            status,
+        })
+    }
+}
+
+impl Resource for Cluster {
+    type DynamicType = ();
+    type Scope = NamespaceResourceScope;
+
+    fn kind((): &()) -> Cow<'_, str> {
+        "Cluster".into()
+    }
+
+    fn group((): &()) -> Cow<'_, str> {
+        CLUSTER_API_GROUP.into()
+    }
+
+    fn version((): &()) -> Cow<'_, str> {
+        ClusterApiVersion::served().as_str().into()
+    }
+
+    fn plural((): &()) -> Cow<'_, str> {
+        "clusters".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+/// Converts a `v1beta2` Cluster into the `v1beta1` layout.
+///
+/// - `controlPlaneRef` and `infrastructureRef` keep the API group in `apiVersion`,
+///   the version is discovered on lookup.
+/// - `topology.classRef` is converted to `topology.class` and `topology.classNamespace`,
+///   other topology fields like `variables` are kept.
+/// - `status.conditions` move to `status.v1beta2.conditions`, and the deprecated
+///   `v1beta1` conditions become `status.conditions`.
+///
+/// Converted objects keep the `v1beta2` API version, so already converted fields are left as is.
+fn from_v1beta2(mut value: Value) -> Value {
+    let namespace = value["metadata"]["namespace"].clone();
+
+    if let Some(spec) = value.get_mut("spec").and_then(Value::as_object_mut) {
+        for key in ["controlPlaneRef", "infrastructureRef"] {
+            let Some(reference) = spec.get_mut(key) else {
+                continue;
+            };
+            if reference.get("apiGroup").is_some() {
+                let converted = json!({
+                    "apiVersion": reference["apiGroup"],
+                    "kind": reference["kind"],
+                    "name": reference["name"],
+                    "namespace": namespace,
+                });
+                *reference = converted;
+            }
+        }
+
+        if let Some(topology) = spec.get_mut("topology").and_then(Value::as_object_mut) {
+            if let Some(class_ref) = topology.remove("classRef") {
+                topology.insert("class".into(), class_ref["name"].clone());
+                if let Some(namespace) = class_ref.get("namespace") {
+                    topology.insert("classNamespace".into(), namespace.clone());
+                }
+            }
+        }
+    }
+
+    if let Some(status) = value.get_mut("status") {
+        if status.get("v1beta2").is_none() {
+            let deprecated = &status["deprecated"]["v1beta1"];
+            let failure_domains = status["failureDomains"].as_array().map(|domains| {
+                domains
+                    .iter()
+                    .filter_map(|domain| {
+                        let name = domain["name"].as_str()?.to_string();
+                        let spec = json!({
+                            "controlPlane": domain["controlPlane"],
+                            "attributes": domain["attributes"],
+                        });
+                        Some((name, spec))
+                    })
+                    .collect::<serde_json::Map<_, _>>()
+            });
+            let converted = json!({
+                "phase": status["phase"],
+                "observedGeneration": status["observedGeneration"],
+                "controlPlaneReady": status["initialization"]["controlPlaneInitialized"],
+                "infrastructureReady": status["initialization"]["infrastructureProvisioned"],
+                "failureDomains": failure_domains,
+                "failureReason": deprecated["failureReason"],
+                "failureMessage": deprecated["failureMessage"],
+                "conditions": deprecated["conditions"],
+                "v1beta2": {
+                    "conditions": status["conditions"],
+                },
+            });
+            *status = converted;
+        }
+    }
+
+    value
+}
+
 impl From<&Cluster> for ObjectMeta {
     fn from(cluster: &Cluster) -> Self {
         Self {
@@ -92,6 +321,42 @@ impl Cluster {
         })
     }
 
+    /// Returns the `ClusterGroups` of the group rules matching the cluster labels.
+    pub(crate) fn to_rule_groups(&self, config: Option<&ClusterConfig>) -> Vec<ClusterGroup> {
+        let Some(config) = config else {
+            return vec![];
+        };
+
+        config
+            .group_rules(self.labels())
+            .map(|rule| {
+                let mut labels = BTreeMap::default();
+                labels.insert(GROUP_RULE_LABEL.to_string(), rule.name.clone());
+
+                let mut match_labels = BTreeMap::default();
+                match_labels.insert(ClusterGroup::member_label(&rule.name), "true".to_string());
+
+                ClusterGroup {
+                    types: Some(TypeMeta::resource::<ClusterGroup>()),
+                    metadata: ObjectMeta {
+                        name: Some(ClusterGroup::rule_group_name(&rule.name)),
+                        namespace: self.namespace(),
+                        labels: Some(labels),
+                        owner_references: self.owner_ref(&()).into_iter().map(Into::into).collect(),
+                        ..Default::default()
+                    },
+                    spec: ClusterGroupSpec {
+                        selector: Some(ClusterGroupSelector {
+                            match_labels: Some(match_labels),
+                            ..Default::default()
+                        }),
+                    },
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
     pub(crate) fn to_cluster(
         self: &Cluster,
         config: Option<&ClusterConfig>,
@@ -101,9 +366,9 @@ impl Cluster {
         let class = self.cluster_class_name();
         let ns = self.namespace().unwrap_or_default();
         let class_namespace = self.cluster_class_namespace().unwrap_or(&ns);
-        let annotations = self.annotations().clone();
+        let annotations = config.propagated_annotations(self.annotations().clone());
         let labels = {
-            let mut labels = self.labels().clone();
+            let mut labels = config.propagated_labels(self.labels().clone());
             if let Some(class) = class {
                 labels.insert(CLUSTER_CLASS_LABEL.to_string(), class.to_string());
                 labels.insert(
@@ -111,10 +376,13 @@ impl Cluster {
                     class_namespace.to_string(),
                 );
             }
+            for rule in config.group_rules(self.labels()) {
+                labels.insert(ClusterGroup::member_label(&rule.name), "true".to_string());
+            }
             labels
         };
 
-        fleet_cluster::Cluster {
+        let mut cluster = fleet_cluster::Cluster {
             types: Some(TypeMeta::resource::<fleet_cluster::Cluster>()),
             metadata: ObjectMeta {
                 annotations: Some(annotations),
@@ -123,7 +391,7 @@ impl Cluster {
                     .set_owner_references
                     .is_some_and(|set| set)
                     .then_some(self.owner_ref(&()).into_iter().collect()),
-                name: config.apply_naming(self.name_any()).into(),
+                name: config.apply_naming(self.meta()).into(),
                 ..self.into()
             },
             #[cfg(feature = "agent-initiated")]
@@ -156,7 +424,10 @@ impl Cluster {
                 ..Default::default()
             },
             ..Default::default()
-        }
+        };
+
+        cluster.track_propagated_keys();
+        cluster
     }
 
     pub(crate) fn to_bundle_ns_mapping(
@@ -170,7 +441,7 @@ impl Cluster {
 
         let match_labels = {
             let mut labels = BTreeMap::default();
-            labels.insert("kubernetes.io/metadata.name".into(), self.namespace()?);
+            labels.insert(NAMESPACE_NAME_LABEL.into(), self.namespace()?);
             Some(labels)
         };
 
@@ -178,6 +449,10 @@ impl Cluster {
             types: Some(TypeMeta::resource::<BundleNamespaceMapping>()),
             metadata: ObjectMeta {
                 name: self.namespace(),
+                labels: Some(BTreeMap::from([(
+                    CLUSTER_CLASS_NAMESPACE_LABEL.to_string(),
+                    class_namespace.clone(),
+                )])),
                 namespace: Some(class_namespace),
                 ..Default::default()
             },
@@ -196,12 +471,13 @@ impl Cluster {
     ) -> Option<ClusterRegistrationToken> {
         use fleet_api_rs::fleet_cluster_registration_token::ClusterRegistrationTokenSpec;
 
-        config?.agent_initiated?.then_some(true)?;
+        let config = config?;
+        config.agent_initiated?.then_some(true)?;
 
         ClusterRegistrationToken {
             metadata: self.into(),
             spec: ClusterRegistrationTokenSpec {
-                ttl: Some("1h".into()),
+                ttl: Some(config.registration_token_ttl()),
             },
             ..Default::default()
         }
@@ -235,4 +511,374 @@ impl Cluster {
     pub(crate) fn cluster_class_name(&self) -> Option<&str> {
         Some(&self.spec.proxy.topology.as_ref()?.class)
     }
+
+    /// Returns the reference to the control plane object of the cluster.
+    pub(crate) fn control_plane_reference(&self) -> Option<ObjectReference> {
+        let reference = self.spec.proxy.control_plane_ref.as_ref()?;
+        Some(ObjectReference {
+            api_version: reference.api_version.clone(),
+            kind: reference.kind.clone(),
+            name: reference.name.clone(),
+            namespace: reference.namespace.clone().or_else(|| self.namespace()),
+            ..Default::default()
+        })
+    }
+
+    /// Returns the reference to the infrastructure cluster object of the cluster.
+    pub(crate) fn infrastructure_reference(&self) -> Option<ObjectReference> {
+        let reference = self.spec.proxy.infrastructure_ref.as_ref()?;
+        Some(ObjectReference {
+            api_version: reference.api_version.clone(),
+            kind: reference.kind.clone(),
+            name: reference.name.clone(),
+            namespace: reference.namespace.clone().or_else(|| self.namespace()),
+            ..Default::default()
+        })
+    }
+
+    /// Checks if the object is the control plane or the infrastructure cluster of the cluster.
+    /// API versions are not compared, as the object may be served in a different version.
+    pub(crate) fn references(&self, object: &DynamicObject) -> bool {
+        let Some(types) = object.types.as_ref() else {
+            return false;
+        };
+
+        [
+            self.control_plane_reference(),
+            self.infrastructure_reference(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|reference| {
+            reference.kind.as_ref() == Some(&types.kind)
+                && reference
+                    .api_version
+                    .as_deref()
+                    .map(|api_version| split_api_version(api_version).0)
+                    == Some(split_api_version(&types.api_version).0)
+                && reference.name == object.metadata.name
+                && reference.namespace == object.metadata.namespace
+        })
+    }
+
+    /// Resolves template source placeholders from the cluster metadata and topology.
+    pub(crate) fn template_placeholder(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "class" => self.cluster_class_name().map(String::from),
+            "classNamespace" => self
+                .cluster_class_namespace()
+                .map(String::from)
+                .or_else(|| self.namespace()),
+            placeholder => meta_placeholder(self.meta(), placeholder),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::ObjectReference;
+    use serde_json::{Value, json};
+
+    use kube::{ResourceExt as _, api::DynamicObject};
+
+    use crate::api::{
+        capi_clusterclass::ClusterClass, fleet_addon_config::ClusterConfig,
+        fleet_clustergroup::GROUP_RULE_LABEL,
+    };
+
+    use super::{Cluster, ClusterApiVersion, split_api_version};
+
+    fn v1beta1_fixture() -> Value {
+        json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {
+                "controlPlaneRef": {
+                    "apiVersion": "controlplane.cluster.x-k8s.io/v1beta1",
+                    "kind": "KubeadmControlPlane",
+                    "name": "test-cp",
+                    "namespace": "default",
+                },
+                "infrastructureRef": {
+                    "apiVersion": "infrastructure.cluster.x-k8s.io/v1beta1",
+                    "kind": "DockerCluster",
+                    "name": "test",
+                    "namespace": "default",
+                },
+                "topology": {
+                    "class": "quick-start",
+                    "classNamespace": "classes",
+                    "version": "v1.33.0",
+                },
+            },
+            "status": {
+                "phase": "Provisioned",
+                "controlPlaneReady": true,
+                "infrastructureReady": true,
+                "conditions": [
+                    {"type": "ControlPlaneInitialized", "status": "True", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                ],
+                "v1beta2": {
+                    "conditions": [
+                        {"type": "Available", "status": "True", "reason": "Available", "message": "", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                    ],
+                },
+            },
+        })
+    }
+
+    fn v1beta2_fixture() -> Value {
+        json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta2",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {
+                "controlPlaneRef": {
+                    "apiGroup": "controlplane.cluster.x-k8s.io",
+                    "kind": "KubeadmControlPlane",
+                    "name": "test-cp",
+                },
+                "infrastructureRef": {
+                    "apiGroup": "infrastructure.cluster.x-k8s.io",
+                    "kind": "DockerCluster",
+                    "name": "test",
+                },
+                "topology": {
+                    "classRef": {"name": "quick-start", "namespace": "classes"},
+                    "version": "v1.33.0",
+                    "controlPlane": {"replicas": 1},
+                    "variables": [
+                        {"name": "region", "value": "eu-west-1"},
+                    ],
+                },
+            },
+            "status": {
+                "phase": "Provisioned",
+                "initialization": {
+                    "infrastructureProvisioned": true,
+                    "controlPlaneInitialized": true,
+                },
+                "failureDomains": [
+                    {"name": "zone-a", "controlPlane": true},
+                ],
+                "conditions": [
+                    {"type": "Available", "status": "True", "reason": "Available", "message": "", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                ],
+                "deprecated": {
+                    "v1beta1": {
+                        "conditions": [
+                            {"type": "ControlPlaneInitialized", "status": "True", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                        ],
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn test_cluster_versions() {
+        for fixture in [v1beta1_fixture(), v1beta2_fixture()] {
+            let cluster: Cluster = serde_json::from_value(fixture).unwrap();
+
+            assert_eq!(cluster.cluster_class_name(), Some("quick-start"));
+            assert_eq!(cluster.cluster_class_namespace(), Some("classes"));
+            assert!(cluster.cluster_ready().is_some());
+
+            let control_plane = cluster.control_plane_reference().unwrap();
+            assert_eq!(control_plane.kind.as_deref(), Some("KubeadmControlPlane"));
+            assert_eq!(control_plane.name.as_deref(), Some("test-cp"));
+            assert_eq!(control_plane.namespace.as_deref(), Some("default"));
+            let (group, _) = split_api_version(control_plane.api_version.as_deref().unwrap());
+            assert_eq!(group, "controlplane.cluster.x-k8s.io");
+
+            let status = serde_json::to_value(&cluster.status).unwrap();
+            assert_eq!(status["conditions"][0]["type"], "ControlPlaneInitialized");
+            assert_eq!(status["v1beta2"]["conditions"][0]["type"], "Available");
+        }
+    }
+
+    #[test]
+    fn test_v1beta2_conversion() {
+        let cluster: Cluster = serde_json::from_value(v1beta2_fixture()).unwrap();
+        assert_eq!(
+            cluster.infrastructure_reference(),
+            Some(ObjectReference {
+                api_version: Some("infrastructure.cluster.x-k8s.io".into()),
+                kind: Some("DockerCluster".into()),
+                name: Some("test".into()),
+                namespace: Some("default".into()),
+                ..Default::default()
+            })
+        );
+
+        let topology = &serde_json::to_value(&cluster.spec).unwrap()["topology"];
+        assert_eq!(topology["class"], "quick-start");
+        assert_eq!(topology["classNamespace"], "classes");
+        assert_eq!(topology["version"], "v1.33.0");
+        assert_eq!(topology["controlPlane"]["replicas"], 1);
+        assert_eq!(
+            topology["variables"],
+            json!([{"name": "region", "value": "eu-west-1"}])
+        );
+
+        let status = serde_json::to_value(&cluster.status).unwrap();
+        assert_eq!(status["failureDomains"]["zone-a"]["controlPlane"], true);
+
+        // Converted clusters keep the v1beta2 API version and are parsed again
+        // when passed through the shared dynamic stream.
+        let mut value = serde_json::to_value(&cluster).unwrap();
+        value["apiVersion"] = json!("cluster.x-k8s.io/v1beta2");
+        let reparsed: Cluster = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            serde_json::to_value(&reparsed.spec).unwrap(),
+            serde_json::to_value(&cluster.spec).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&reparsed.status).unwrap(),
+            serde_json::to_value(&cluster.status).unwrap()
+        );
+
+        let class: ClusterClass = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta2",
+            "kind": "ClusterClass",
+            "metadata": {"name": "quick-start", "namespace": "classes"},
+            "spec": {
+                "infrastructure": {"templateRef": {"apiVersion": "infrastructure.cluster.x-k8s.io/v1beta2", "kind": "DockerClusterTemplate", "name": "quick-start"}},
+            },
+            "status": {"conditions": []},
+        }))
+        .unwrap();
+        assert_eq!(class.metadata.name.as_deref(), Some("quick-start"));
+    }
+
+    #[test]
+    fn test_rule_groups() {
+        let mut fixture = v1beta1_fixture();
+        fixture["metadata"]["labels"] = json!({"location": "edge"});
+        let cluster: Cluster = serde_json::from_value(fixture).unwrap();
+        let config = ClusterConfig {
+            cluster_groups: serde_json::from_value(json!([
+                {"name": "edge", "selector": {"matchLabels": {"location": "edge"}}},
+                {"name": "core", "selector": {"matchLabels": {"location": "core"}}},
+            ]))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let groups = cluster.to_rule_groups(Some(&config));
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.metadata.name.as_deref(), Some("rule-edge"));
+        assert_eq!(group.metadata.namespace.as_deref(), Some("default"));
+        assert_eq!(
+            group.labels().get(GROUP_RULE_LABEL),
+            Some(&"edge".to_string())
+        );
+        assert_eq!(
+            group
+                .spec
+                .selector
+                .as_ref()
+                .and_then(|selector| selector.match_labels.as_ref())
+                .and_then(|labels| labels.get("group.fleet.addons.cluster.x-k8s.io/edge")),
+            Some(&"true".to_string())
+        );
+
+        let fleet = cluster.to_cluster(Some(&config));
+        assert_eq!(
+            fleet
+                .labels()
+                .get("group.fleet.addons.cluster.x-k8s.io/edge"),
+            Some(&"true".to_string())
+        );
+        assert!(
+            !fleet
+                .labels()
+                .contains_key("group.fleet.addons.cluster.x-k8s.io/core")
+        );
+        assert!(cluster.to_rule_groups(None).is_empty());
+    }
+
+    #[test]
+    fn test_served_version_conditions() {
+        let condition = json!({
+            "type": "FleetTemplateValuesResolved",
+            "status": "False",
+            "severity": "Warning",
+            "reason": "TemplateResolutionFailed",
+        });
+
+        assert_eq!(
+            ClusterApiVersion::V1beta1.to_condition(condition.clone()),
+            condition
+        );
+
+        let converted = ClusterApiVersion::V1beta2.to_condition(condition);
+        assert!(converted.get("severity").is_none());
+        assert_eq!(converted["message"], "");
+
+        assert_eq!(
+            split_api_version("controlplane.cluster.x-k8s.io/v1beta1"),
+            ("controlplane.cluster.x-k8s.io", Some("v1beta1"))
+        );
+        assert_eq!(
+            split_api_version("controlplane.cluster.x-k8s.io"),
+            ("controlplane.cluster.x-k8s.io", None)
+        );
+        assert_eq!(split_api_version("v1"), ("", Some("v1")));
+    }
+
+    #[test]
+    fn test_cluster_references() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {
+                "controlPlaneRef": {
+                    "apiVersion": "controlplane.cluster.x-k8s.io/v1beta1",
+                    "kind": "KubeadmControlPlane",
+                    "name": "test-cp",
+                },
+                "infrastructureRef": {
+                    "apiVersion": "infrastructure.cluster.x-k8s.io/v1beta1",
+                    "kind": "DockerCluster",
+                    "name": "test",
+                    "namespace": "default",
+                },
+            },
+        }))
+        .unwrap();
+
+        let object = |api_version: &str, kind: &str, name: &str| -> DynamicObject {
+            serde_json::from_value(json!({
+                "apiVersion": api_version,
+                "kind": kind,
+                "metadata": {"name": name, "namespace": "default"},
+            }))
+            .unwrap()
+        };
+
+        assert!(cluster.references(&object(
+            "controlplane.cluster.x-k8s.io/v1beta2",
+            "KubeadmControlPlane",
+            "test-cp"
+        )));
+        assert!(cluster.references(&object(
+            "infrastructure.cluster.x-k8s.io/v1beta1",
+            "DockerCluster",
+            "test"
+        )));
+        assert!(!cluster.references(&object(
+            "controlplane.cluster.x-k8s.io/v1beta1",
+            "KubeadmControlPlane",
+            "other"
+        )));
+        assert!(!cluster.references(&object(
+            "infrastructure.cluster.x-k8s.io/v1beta1",
+            "KubeadmControlPlane",
+            "test-cp"
+        )));
+    }
 }
@@ -8,21 +8,98 @@ mod prelude {
     pub use serde::{Deserialize, Serialize};
     pub use std::collections::BTreeMap;
 }
+use std::borrow::Cow;
+
 use cluster_api_rs::capi_clusterclass::{ClusterClassSpec, ClusterClassStatus};
+use k8s_openapi::NamespaceResourceScope;
+use kube::{
+    Resource,
+    api::{ObjectMeta, TypeMeta},
+};
+use serde_json::{Value, json};
+
+use super::capi_cluster::{CLUSTER_API_GROUP, ClusterApiVersion};
 
 use self::prelude::*;
 
 /// `ClusterClassProxy` describes the desired state of the `ClusterClass`.
-#[derive(CustomResource, Serialize, Deserialize, Clone, Debug, JsonSchema)]
-#[kube(
-    group = "cluster.x-k8s.io",
-    version = "v1beta1",
-    kind = "ClusterClass",
-    plural = "clusterclasses"
-)]
-#[kube(namespaced)]
-#[kube(status = "ClusterClassStatus")]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct ClusterClassProxy {
     #[serde(flatten)]
     pub proxy: ClusterClassSpec,
 }
+
+/// CAPI `ClusterClass` in the `v1beta1` layout. Requests use the served API version.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "Value")]
+pub struct ClusterClass {
+    #[serde(flatten)]
+    pub types: Option<TypeMeta>,
+    pub metadata: ObjectMeta,
+    pub spec: ClusterClassProxy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ClusterClassStatus>,
+}
+
+#[derive(Deserialize)]
+struct ClusterClassFields {
+    #[serde(default)]
+    metadata: ObjectMeta,
+    spec: ClusterClassProxy,
+    #[serde(default)]
+    status: Option<ClusterClassStatus>,
+}
+
+impl TryFrom<Value> for ClusterClass {
+    type Error = serde_json::Error;
+
+    fn try_from(mut value: Value) -> Result<Self, Self::Error> {
+        // Only the metadata of the ClusterClass is used, so the `v1beta2` spec and status,
+        // which have a different layout, are dropped instead of converted.
+        if ClusterApiVersion::V1beta2.matches(&value) {
+            value["spec"] = json!({});
+            value["status"] = Value::Null;
+        }
+
+        let ClusterClassFields {
+            metadata,
+            spec,
+            status,
+        } = serde_json::from_value(value)?;
+        Ok(Self {
+            types: Some(TypeMeta::resource::<Self>()),
+            metadata,
+            spec,
+            status,
+        })
+    }
+}
+
+impl Resource for ClusterClass {
+    type DynamicType = ();
+    type Scope = NamespaceResourceScope;
+
+    fn kind((): &()) -> Cow<'_, str> {
+        "ClusterClass".into()
+    }
+
+    fn group((): &()) -> Cow<'_, str> {
+        CLUSTER_API_GROUP.into()
+    }
+
+    fn version((): &()) -> Cow<'_, str> {
+        ClusterApiVersion::served().as_str().into()
+    }
+
+    fn plural((): &()) -> Cow<'_, str> {
+        "clusterclasses".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
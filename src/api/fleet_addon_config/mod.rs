@@ -0,0 +1,1564 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr, time::Duration};
+
+use crate::api::comparable::ResourceDiff;
+use educe::Educe;
+use fleet_api_rs::fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations};
+use k8s_openapi::{
+    api::core::v1::{ConfigMap, ObjectReference},
+    apimachinery::pkg::apis::meta::v1::{Condition, LabelSelector},
+};
+use kube::{
+    CustomResource, KubeSchema, Resource,
+    api::{ObjectMeta, TypeMeta},
+    core::{ParseExpressionError, Selector},
+};
+use regex::Regex;
+use schemars::{
+    JsonSchema,
+    schema::{InstanceType, Schema, SchemaObject},
+};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize, ser};
+use serde_with::{DisplayFromStr, serde_as};
+use serde_yaml::Value;
+use sha2::{Digest as _, Sha256};
+
+pub mod v1alpha2;
+
+pub const AGENT_NAMESPACE: &str = "fleet-addon-agent";
+/// Namespace of the Fleet installation.
+pub const FLEET_NAMESPACE: &str = "cattle-fleet-system";
+/// Helm repository hosting the Fleet charts.
+pub const FLEET_REPOSITORY: &str = "https://rancher.github.io/fleet-helm-charts/";
+/// Default TTL of the `ClusterRegistrationToken` created for agent initiated connection.
+#[cfg(feature = "agent-initiated")]
+pub const DEFAULT_REGISTRATION_TOKEN_TTL: &str = "1h";
+pub const EXPERIMENTAL_OCI_STORAGE: &str = "EXPERIMENTAL_OCI_STORAGE";
+pub const EXPERIMENTAL_HELM_OPS: &str = "EXPERIMENTAL_HELM_OPS";
+
+/// Maximum length of the Fleet cluster name, matching the DNS label limit.
+const MAX_NAME_LENGTH: usize = 63;
+/// Number of hash bytes appended to truncated names.
+const NAME_HASH_BYTES: usize = 4;
+
+/// This provides a config for fleet addon functionality
+#[derive(CustomResource, Deserialize, Serialize, Clone, Default, Debug, KubeSchema, PartialEq)]
+#[kube(
+    kind = "FleetAddonConfig",
+    group = "addons.cluster.x-k8s.io",
+    version = "v1alpha1",
+    status = "FleetAddonConfigStatus",
+    validation = "self.metadata.name == 'fleet-addon-config'"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAddonConfigSpec {
+    /// Enable clusterClass controller functionality.
+    ///
+    /// This will create Fleet `ClusterGroups` for each `ClusterClaster` with the same name.
+    pub cluster_class: Option<ClusterClassConfig>,
+
+    /// Enable Cluster config funtionality.
+    ///
+    /// This will create Fleet Cluster for each Cluster with the same name.
+    /// In case the cluster specifies topology.class, the name of the `ClusterClass`
+    /// will be added to the Fleet Cluster labels.
+    pub cluster: Option<ClusterConfig>,
+
+    // Fleet chart configuratoin options
+    pub config: Option<FleetConfig>,
+
+    // Fleet chart installation options
+    pub install: Option<FleetInstall>,
+}
+
+impl Default for FleetAddonConfig {
+    fn default() -> Self {
+        Self {
+            metadata: ObjectMeta::default(),
+            spec: FleetAddonConfigSpec {
+                cluster_class: Some(ClusterClassConfig::default()),
+                cluster: Some(ClusterConfig::default()),
+                config: Some(FleetConfig::default()),
+                ..Default::default()
+            },
+            status: Option::default(),
+        }
+    }
+}
+impl ResourceDiff for FleetAddonConfig {
+    fn diff(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAddonConfigStatus {
+    pub installed_version: Option<String>,
+    /// Hash of the values the Fleet chart was last installed or upgraded with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values_hash: Option<String>,
+    /// conditions represents the observations of a Fleet addon current state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterClassConfig {
+    /// Setting to disable setting owner references on the created resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_owner_references: Option<bool>,
+
+    /// Allow to patch resources, maintaining the desired state.
+    /// If is not set, resources will only be re-created in case of removal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_resource: Option<bool>,
+
+    /// Periodic removal of `ClusterGroups` and `BundleNamespaceMappings` left behind
+    /// by removed `ClusterClasses` or clusters. Disabled if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub garbage_collection: Option<GarbageCollection>,
+
+    /// Filter for labels propagated from the `ClusterClass` to the Fleet `ClusterGroups`.
+    /// If not set, all labels are propagated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_propagation: Option<MetadataFilter>,
+}
+
+impl Default for ClusterClassConfig {
+    fn default() -> Self {
+        Self {
+            patch_resource: Some(true),
+            set_owner_references: Some(true),
+            garbage_collection: None,
+            label_propagation: None,
+        }
+    }
+}
+
+/// Default interval between garbage collection runs in seconds.
+pub const DEFAULT_GARBAGE_COLLECTION_INTERVAL: u32 = 600;
+
+/// `GarbageCollection` removes orphaned `ClusterGroups` and `BundleNamespaceMappings`
+/// carrying the `ClusterClass` labels.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageCollection {
+    /// Interval between garbage collection runs in seconds. Defaults to 600.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_seconds: Option<u32>,
+
+    /// Only publish events for orphaned objects, without removing them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+}
+
+impl GarbageCollection {
+    pub(crate) fn interval(&self) -> Duration {
+        Duration::from_secs(
+            self.interval_seconds
+                .unwrap_or(DEFAULT_GARBAGE_COLLECTION_INTERVAL)
+                .max(1)
+                .into(),
+        )
+    }
+
+    pub(crate) fn dry_run(&self) -> bool {
+        self.dry_run.is_some_and(|dry_run| dry_run)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfig {
+    /// Apply a `ClusterGroup` for a `ClusterClass` referenced from a different namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_class_group: Option<bool>,
+
+    /// Allow to patch resources, maintaining the desired state.
+    /// If is not set, resources will only be re-created in case of removal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_resource: Option<bool>,
+
+    /// Setting to disable setting owner references on the created resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_owner_references: Option<bool>,
+
+    /// Naming settings for the fleet cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub naming: Option<NamingStrategy>,
+
+    /// Namespace selection for the fleet agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_namespace: Option<String>,
+
+    /// Agent taint toleration settings for every cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_tolerations: Option<Vec<ClusterAgentTolerations>>,
+
+    /// Host network allows to deploy agent configuration using hostNetwork: true setting
+    /// which eludes dependency on the CNI configuration for the cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_network: Option<bool>,
+
+    /// `AgentEnvVars` are extra environment variables to be added to the agent deployment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_env_vars: Option<Vec<ClusterAgentEnvVars>>,
+
+    /// Filter for labels propagated from the CAPI cluster to the Fleet cluster.
+    /// If not set, all labels are propagated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_propagation: Option<MetadataFilter>,
+
+    /// Filter for annotations propagated from the CAPI cluster to the Fleet cluster.
+    /// If not set, all annotations are propagated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<MetadataFilter>,
+
+    /// Static labels added to every Fleet cluster, overriding propagated labels with the same key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_labels: Option<BTreeMap<String, String>>,
+
+    /// Additional objects exposed in the Fleet cluster template values under their keys,
+    /// next to `Cluster`, `ControlPlane` and `InfrastructureCluster`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_sources: Option<Vec<TemplateSource>>,
+
+    /// Field allowlist and size limit for the Fleet cluster template values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_values: Option<TemplateValuesConfig>,
+
+    /// Conditions required on the CAPI cluster before it is imported, in addition to
+    /// the initialized control plane.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness_gates: Option<Vec<ReadinessGate>>,
+
+    /// Rules creating Fleet `ClusterGroups` for CAPI clusters matching a label selector,
+    /// independently of the `ClusterClass` topology.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_groups: Option<Vec<ClusterGroupRule>>,
+
+    /// Import settings for the CAPI cluster. Allows to import clusters based on a set of labels,
+    /// set on the cluster or the namespace.
+    #[serde(flatten)]
+    pub selectors: Selectors,
+
+    #[cfg(feature = "agent-initiated")]
+    /// Prepare initial cluster for agent initiated connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_initiated: Option<bool>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// TTL of the `ClusterRegistrationToken` created for agent initiated connection,
+    /// as a duration string like `24h`. Defaults to `1h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_token_ttl: Option<String>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// Delivery of the Fleet agent into the workload cluster for agent initiated connection.
+    /// If not set, the agent has to be installed manually.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_bootstrap: Option<AgentBootstrap>,
+}
+
+/// `AgentBootstrap` delivers the Fleet agent manifests into the workload cluster,
+/// so the agent registers with the `ClusterRegistrationToken` of the cluster.
+#[cfg(feature = "agent-initiated")]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentBootstrap {
+    /// Method used to apply the agent manifests in the workload cluster.
+    #[serde(default)]
+    pub delivery: AgentBootstrapDelivery,
+
+    /// Fleet agent image. Defaults to `rancher/fleet-agent` with the installed Fleet version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+/// `AgentBootstrapDelivery` selects how the agent manifests reach the workload cluster.
+#[cfg(feature = "agent-initiated")]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema, Default, PartialEq)]
+pub enum AgentBootstrapDelivery {
+    /// Manifests are applied by CAPI with a `ClusterResourceSet`.
+    #[default]
+    ClusterResourceSet,
+
+    /// Manifests are applied by the controller, using the `<cluster>-kubeconfig` secret.
+    Kubeconfig,
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[resource(inherit = ConfigMap)]
+pub struct FleetSettings {
+    #[serde(flatten, default)]
+    pub types: Option<TypeMeta>,
+    pub metadata: ObjectMeta,
+    pub data: Option<FleetSettingsSpec>,
+}
+
+impl ResourceDiff for FleetSettings {
+    fn diff(&self, other: &Self) -> bool {
+        self.data != other.data
+    }
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Default, Clone, Debug, Educe)]
+#[educe(PartialEq)]
+pub struct FleetSettingsSpec {
+    #[serde(default)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub fleet: FleetChartValues,
+
+    #[educe(PartialEq(ignore))]
+    #[serde(flatten)]
+    pub other: Value,
+}
+
+impl FromStr for FleetChartValues {
+    type Err = serde_yaml::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_yaml::from_str(s)
+    }
+}
+
+impl Display for FleetChartValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&serde_yaml::to_string(self).map_err(ser::Error::custom)?)
+    }
+}
+
+impl FleetAddonConfigSpec {
+    /// Returns reference to `FeatureGates` if defined.
+    pub(crate) fn feature_gates(&self) -> Option<&FeatureGates> {
+        self.config.as_ref()?.feature_gates.as_ref()
+    }
+
+    /// Returns the value of `bootstrap_local_cluster` if defined.
+    pub(crate) fn bootstrap(&self) -> Option<bool> {
+        self.config.as_ref()?.bootstrap_local_cluster
+    }
+
+    /// Returns the namespace Fleet is installed in.
+    pub(crate) fn fleet_namespace(&self) -> &str {
+        self.install
+            .as_ref()
+            .map_or(FLEET_NAMESPACE, FleetInstall::namespace)
+    }
+
+    /// Fills unset settings of the enabled sections with the values from `FleetAddonConfig::default()`.
+    /// Sections which are not set are left untouched, as they disable the corresponding functionality.
+    pub(crate) fn apply_defaults(&mut self) {
+        if let Some(cluster_class) = self.cluster_class.as_mut() {
+            let defaults = ClusterClassConfig::default();
+            cluster_class.patch_resource = cluster_class.patch_resource.or(defaults.patch_resource);
+            cluster_class.set_owner_references = cluster_class
+                .set_owner_references
+                .or(defaults.set_owner_references);
+        }
+
+        if let Some(cluster) = self.cluster.as_mut() {
+            let defaults = ClusterConfig::default();
+            cluster.apply_class_group = cluster.apply_class_group.or(defaults.apply_class_group);
+            cluster.patch_resource = cluster.patch_resource.or(defaults.patch_resource);
+            cluster.set_owner_references = cluster
+                .set_owner_references
+                .or(defaults.set_owner_references);
+            cluster.agent_namespace = cluster.agent_namespace.take().or(defaults.agent_namespace);
+            cluster.host_network = cluster.host_network.or(defaults.host_network);
+            #[cfg(feature = "agent-initiated")]
+            {
+                cluster.agent_initiated = cluster.agent_initiated.or(defaults.agent_initiated);
+            }
+        }
+
+        if let Some(config) = self.config.as_mut() {
+            config.feature_gates = config
+                .feature_gates
+                .take()
+                .or(FleetConfig::default().feature_gates);
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub(crate) fn agent_install_namespace(&self) -> String {
+        self.agent_namespace
+            .clone()
+            .unwrap_or(AGENT_NAMESPACE.to_string())
+    }
+
+    pub(crate) fn agent_tolerations(&self) -> Vec<ClusterAgentTolerations> {
+        let agent_tolerations = vec![
+            ClusterAgentTolerations {
+                effect: Some("NoSchedule".into()),
+                operator: Some("Exists".into()),
+                key: Some("node.kubernetes.io/not-ready".into()),
+                ..Default::default()
+            },
+            ClusterAgentTolerations {
+                effect: Some("NoSchedule".into()),
+                operator: Some("Exists".into()),
+                key: Some("node.cluster.x-k8s.io/uninitialized".into()),
+                ..Default::default()
+            },
+            ClusterAgentTolerations {
+                effect: Some("NoSchedule".into()),
+                operator: Some("Equal".into()),
+                key: Some("node.cloudprovider.kubernetes.io/uninitialized".into()),
+                value: Some("true".into()),
+                ..Default::default()
+            },
+        ];
+
+        self.agent_tolerations.clone().unwrap_or(agent_tolerations)
+    }
+
+    #[cfg(feature = "agent-initiated")]
+    pub(crate) fn agent_initiated_connection(&self) -> bool {
+        self.agent_initiated.filter(|&set| set).is_some()
+    }
+
+    #[cfg(feature = "agent-initiated")]
+    pub(crate) fn registration_token_ttl(&self) -> String {
+        self.registration_token_ttl
+            .clone()
+            .unwrap_or(DEFAULT_REGISTRATION_TOKEN_TTL.to_string())
+    }
+
+    pub(crate) fn apply_naming(&self, meta: &ObjectMeta) -> String {
+        let strategy = self.naming.clone().unwrap_or_default();
+        let name = meta.name.clone().unwrap_or_default();
+        strategy.render(meta).unwrap_or(name)
+    }
+
+    pub(crate) fn apply_class_group(&self) -> bool {
+        self.apply_class_group.is_some_and(|enabled| enabled)
+    }
+
+    /// Returns the group rules matching the CAPI cluster labels.
+    pub(crate) fn group_rules<'a>(
+        &'a self,
+        labels: &'a BTreeMap<String, String>,
+    ) -> impl Iterator<Item = &'a ClusterGroupRule> {
+        self.cluster_groups
+            .iter()
+            .flatten()
+            .filter(|rule| rule.matches(labels))
+    }
+
+    /// Returns the CAPI cluster labels to set on the Fleet cluster, including extra labels.
+    pub(crate) fn propagated_labels(
+        &self,
+        labels: BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        let mut labels = match &self.label_propagation {
+            Some(filter) => filter.filter(labels),
+            None => labels,
+        };
+        labels.extend(self.extra_labels.clone().unwrap_or_default());
+        labels
+    }
+
+    /// Returns the CAPI cluster annotations to set on the Fleet cluster.
+    pub(crate) fn propagated_annotations(
+        &self,
+        annotations: BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        match &self.annotation_propagation {
+            Some(filter) => filter.filter(annotations),
+            None => annotations,
+        }
+    }
+}
+
+/// `MetadataFilter` selects label or annotation keys by include and exclude patterns.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataFilter {
+    /// Keys matching any of the patterns are propagated. If empty, all keys are included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<KeyPattern>,
+
+    /// Keys matching any of the patterns are not propagated. Takes precedence over include.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<KeyPattern>,
+}
+
+/// `KeyPattern` matches a label or annotation key.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyPattern {
+    /// Match keys starting with the prefix
+    Prefix(String),
+
+    /// Match keys with the regular expression
+    Regex(String),
+}
+
+impl MetadataFilter {
+    pub(crate) fn filter(&self, map: BTreeMap<String, String>) -> BTreeMap<String, String> {
+        let include = PatternSet::new(&self.include);
+        let exclude = PatternSet::new(&self.exclude);
+
+        map.into_iter()
+            .filter(|(key, _)| {
+                (self.include.is_empty() || include.matches(key)) && !exclude.matches(key)
+            })
+            .collect()
+    }
+
+    /// Returns all patterns of the filter.
+    pub(crate) fn patterns(&self) -> impl Iterator<Item = &KeyPattern> {
+        self.include.iter().chain(self.exclude.iter())
+    }
+}
+
+/// `PatternSet` holds the key patterns compiled once for matching all keys of a map.
+struct PatternSet<'a> {
+    prefixes: Vec<&'a str>,
+    expressions: Vec<Regex>,
+}
+
+impl<'a> PatternSet<'a> {
+    fn new(patterns: &'a [KeyPattern]) -> Self {
+        let mut set = Self {
+            prefixes: vec![],
+            expressions: vec![],
+        };
+        for pattern in patterns {
+            match pattern {
+                KeyPattern::Prefix(prefix) => set.prefixes.push(prefix),
+                // Invalid expressions are rejected by the admission webhook and match nothing
+                KeyPattern::Regex(expression) => {
+                    set.expressions.extend(Regex::new(expression).ok())
+                }
+            }
+        }
+        set
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+            || self.expressions.iter().any(|re| re.is_match(key))
+    }
+}
+
+impl KeyPattern {
+    /// Checks the regular expression of the pattern.
+    pub(crate) fn validate(&self) -> Result<(), regex::Error> {
+        match self {
+            Self::Prefix(_) => Ok(()),
+            Self::Regex(expression) => Regex::new(expression).map(|_| ()),
+        }
+    }
+}
+
+/// Keys of the built-in template values, which can't be used by a `TemplateSource`.
+pub const RESERVED_TEMPLATE_KEYS: [&str; 3] = ["Cluster", "ControlPlane", "InfrastructureCluster"];
+
+/// `TemplateSource` references namespaced objects exposed in the Fleet cluster template values.
+///
+/// Name, namespace and selector label values support `{{name}}`, `{{namespace}}`, `{{class}}`,
+/// `{{classNamespace}}` and `{{labels.<key>}}` placeholders rendered from the CAPI cluster.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSource {
+    /// Key of the resolved object in the template values.
+    pub key: String,
+
+    /// API version of the referenced object.
+    pub api_version: String,
+
+    /// Kind of the referenced object.
+    pub kind: String,
+
+    /// Name of the referenced object. If not set, all objects matching the selector
+    /// are exposed as a list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Namespace of the referenced object. Defaults to the CAPI cluster namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Label selector for the listed objects, used when name is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<LabelSelector>,
+
+    /// Keys of the `data` exposed for a `ConfigMap` or `Secret`. Required for a `Secret`,
+    /// which is reduced to its name, namespace and the listed keys, as Fleet clusters
+    /// are readable more widely than secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<String>>,
+}
+
+impl TemplateSource {
+    /// Checks if the source references core `Secrets`.
+    pub(crate) fn is_secret(&self) -> bool {
+        self.api_version == "v1" && self.kind == "Secret"
+    }
+
+    /// Returns the selector with rendered label values.
+    pub(crate) fn render_selector(
+        &self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Selector, ParseExpressionError> {
+        let mut selector = self.selector.clone().unwrap_or_default();
+        if let Some(labels) = selector.match_labels.as_mut() {
+            for value in labels.values_mut() {
+                *value = render_placeholders(value, &lookup);
+            }
+        }
+
+        selector.try_into()
+    }
+}
+
+/// `ReadinessGate` requires a condition on the CAPI cluster, such as `InfrastructureReady`
+/// or a provider specific condition. Both CAPI `v1beta1` and `v1beta2` conditions are considered.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessGate {
+    /// Type of the required condition.
+    pub condition_type: String,
+
+    /// Required status of the condition. Defaults to `True`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+impl ReadinessGate {
+    pub(crate) fn status(&self) -> &str {
+        self.status.as_deref().unwrap_or("True")
+    }
+}
+
+/// `ClusterGroupRule` maintains a Fleet `ClusterGroup` in the namespace of every CAPI cluster
+/// matching the selector. Matching Fleet clusters are labeled as members of the group.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterGroupRule {
+    /// Name of the group rule. The `ClusterGroup` is named `rule-<name>`.
+    /// Must be a valid label name, as it is part of the member label key.
+    pub name: String,
+
+    /// Label selector for the CAPI clusters in the group.
+    pub selector: LabelSelector,
+}
+
+impl ClusterGroupRule {
+    /// Checks if the CAPI cluster labels match the rule selector.
+    /// Invalid selectors match no cluster.
+    pub(crate) fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        Selector::try_from(self.selector.clone()).is_ok_and(|selector| selector.matches(labels))
+    }
+}
+
+/// Default limit for the serialized Fleet cluster template values size in bytes.
+pub const DEFAULT_TEMPLATE_VALUES_MAX_SIZE: u32 = 512 * 1024;
+
+/// `TemplateValuesConfig` limits the content of the Fleet cluster template values.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateValuesConfig {
+    /// Field allowlist per template values key, such as `Cluster`, `ControlPlane` or a template
+    /// source key. Fields are dot-separated paths, like `spec.clusterNetwork`, where dots in
+    /// keys are escaped with `\`. Values without a configured allowlist are exposed as a whole.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+
+    /// Maximum size of the serialized template values in bytes. Larger values are not written
+    /// to the Fleet cluster, and a warning event is published instead. Defaults to 512KiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u32>,
+}
+
+impl TemplateValuesConfig {
+    /// Returns the configured maximum size of the template values, or the default one.
+    pub(crate) fn max_size(&self) -> usize {
+        self.max_size.unwrap_or(DEFAULT_TEMPLATE_VALUES_MAX_SIZE) as usize
+    }
+
+    /// Reduces the value exposed under the key to the allowed fields.
+    pub(crate) fn select(&self, key: &str, value: serde_json::Value) -> serde_json::Value {
+        let Some(fields) = self.fields.get(key) else {
+            return value;
+        };
+
+        let mut selected = serde_json::Value::Null;
+        for field in fields {
+            if let Some(field) = select_field(&value, &field_path(field)) {
+                merge_fields(&mut selected, field);
+            }
+        }
+        selected
+    }
+}
+
+fn field_path(field: &str) -> Vec<String> {
+    let mut path = vec![];
+    let mut segment = String::new();
+    let mut chars = field
+        .trim_start_matches('$')
+        .trim_start_matches('.')
+        .chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => segment.extend(chars.next()),
+            '.' => path.push(std::mem::take(&mut segment)),
+            c => segment.push(c),
+        }
+    }
+    path.push(segment);
+    path
+}
+
+/// Selects the value at the path, keeping the surrounding structure. Lists are traversed
+/// element-wise, and elements without the field are kept as `null` to preserve the order.
+fn select_field(value: &serde_json::Value, path: &[String]) -> Option<serde_json::Value> {
+    let Some((key, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+
+    match value {
+        serde_json::Value::Object(object) => {
+            let field = select_field(object.get(key)?, rest)?;
+            Some(serde_json::Value::Object(
+                [(key.clone(), field)].into_iter().collect(),
+            ))
+        }
+        serde_json::Value::Array(items) => Some(serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| select_field(item, path).unwrap_or_default())
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn merge_fields(target: &mut serde_json::Value, source: serde_json::Value) {
+    match (target, source) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_fields(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(target), serde_json::Value::Array(source)) => {
+            for (index, value) in source.into_iter().enumerate() {
+                match target.get_mut(index) {
+                    Some(existing) => merge_fields(existing, value),
+                    None => target.push(value),
+                }
+            }
+        }
+        (target, source) if target.is_null() => *target = source,
+        _ => {}
+    }
+}
+
+/// `NamingStrategy` is controlling Fleet cluster naming
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+pub struct NamingStrategy {
+    /// Specify a prefix for the Cluster name, applied to created Fleet cluster
+    pub prefix: Option<String>,
+    /// Specify a suffix for the Cluster name, applied to created Fleet cluster
+    pub suffix: Option<String>,
+    /// Specify a template for the Fleet cluster name, rendered from the Cluster metadata
+    /// before prefix and suffix are applied. Supports `{{name}}`, `{{namespace}}`
+    /// and `{{labels.<key>}}` placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            apply_class_group: Some(true),
+            set_owner_references: Some(true),
+            naming: Option::default(),
+            agent_namespace: AGENT_NAMESPACE.to_string().into(),
+            host_network: Some(true),
+            #[cfg(feature = "agent-initiated")]
+            agent_initiated: Some(true),
+            #[cfg(feature = "agent-initiated")]
+            registration_token_ttl: None,
+            #[cfg(feature = "agent-initiated")]
+            agent_bootstrap: None,
+            selectors: Selectors::default(),
+            patch_resource: Some(true),
+            agent_env_vars: None,
+            agent_tolerations: None,
+            label_propagation: None,
+            annotation_propagation: None,
+            extra_labels: None,
+            template_sources: None,
+            template_values: None,
+            readiness_gates: None,
+            cluster_groups: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetConfig {
+    /// fleet server url configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<Server>,
+    /// feature gates controlling experimental features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature_gates: Option<FeatureGates>,
+    /// Enable auto-installation of a fleet agent in the local cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootstrap_local_cluster: Option<bool>,
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self {
+            server: Option::default(),
+            feature_gates: Some(FeatureGates::default()),
+            bootstrap_local_cluster: None,
+        }
+    }
+}
+
+/// Feature toggles for enabling or disabling experimental functionality.
+/// This struct controls access to specific experimental features.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureGates {
+    /// Enables experimental OCI  storage support.
+    pub experimental_oci_storage: bool,
+
+    /// Enables experimental Helm operations support.
+    pub experimental_helm_ops: bool,
+
+    // Enables syncing of feature gates to a ConfigMap.
+    pub config_map: Option<FeaturesConfigMap>,
+}
+
+impl Display for FeatureGates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let oci = self.experimental_oci_storage;
+        let helm_ops = self.experimental_helm_ops;
+        let config_map = self.config_map_ref();
+        f.write_str(&format!("ref={config_map:#?}, oci={oci}, helm={helm_ops}"))
+    }
+}
+
+impl FeatureGates {
+    /// Returns reference to a `ConfigMap` if defined.
+    pub(crate) fn config_map_ref(&self) -> Option<&ObjectReference> {
+        self.config_map.as_ref()?.reference.as_ref()
+    }
+
+    /// Merge the feature gates environment variables with a provided optional input.
+    pub(crate) fn merge_features(&self, settings: &mut FleetSettingsSpec) {
+        // Sync the feature flags to the map.
+        let env_map = settings.fleet.extra_env.get_or_insert_default();
+
+        match env_map
+            .iter_mut()
+            .find(|env| env.name == EXPERIMENTAL_HELM_OPS)
+        {
+            Some(helm_ops) => helm_ops.value = self.experimental_helm_ops.to_string(),
+            None => env_map.push(EnvironmentVariable {
+                name: EXPERIMENTAL_HELM_OPS.to_string(),
+                value: self.experimental_helm_ops.to_string(),
+            }),
+        }
+
+        match env_map
+            .iter_mut()
+            .find(|env| env.name == EXPERIMENTAL_OCI_STORAGE)
+        {
+            Some(helm_ops) => helm_ops.value = self.experimental_oci_storage.to_string(),
+            None => env_map.push(EnvironmentVariable {
+                name: EXPERIMENTAL_OCI_STORAGE.to_string(),
+                value: self.experimental_oci_storage.to_string(),
+            }),
+        }
+    }
+}
+
+impl Default for FeatureGates {
+    fn default() -> Self {
+        Self {
+            // Unless is set otherwise, these features are enabled by CAAPF
+            experimental_oci_storage: true,
+            experimental_helm_ops: true,
+            config_map: None,
+        }
+    }
+}
+
+/// `FeaturesConfigMap` references a `ConfigMap` where to apply feature flags.
+/// If a `ConfigMap` is referenced, the controller will update it instead of upgrading the Fleet chart.
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeaturesConfigMap {
+    // The reference to a ConfigMap resource
+    #[serde(rename = "ref")]
+    pub reference: Option<ObjectReference>,
+}
+
+/// `FleetChartValues` represents Fleet chart values.
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetChartValues {
+    pub extra_env: Option<Vec<EnvironmentVariable>>,
+    #[serde(flatten)]
+    pub other: Value,
+}
+
+/// `EnvironmentVariable` is a simple name/value pair.
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentVariable {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetInstall {
+    /// Chart version to install
+    #[serde(flatten)]
+    pub install_version: Install,
+
+    /// Helm repository URL to install the Fleet charts from.
+    /// Defaults to the upstream Fleet Helm repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+
+    /// OCI reference hosting the `fleet` and `fleet-crd` charts, e.g. `oci://registry.example.com/charts`.
+    /// Takes precedence over the repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oci_repository: Option<String>,
+
+    /// Reference to a secret with `username` and `password` keys, used to authenticate
+    /// with the Helm repository or OCI registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_secret_ref: Option<ObjectReference>,
+
+    /// Reference to a config map with the `ca.crt` key, containing the CA bundle
+    /// used to verify the Helm repository or OCI registry certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_ref: Option<ObjectReference>,
+
+    /// Namespace to install Fleet into. Defaults to `cattle-fleet-system`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Allow pre-release versions to satisfy the version constraint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_prerelease: Option<bool>,
+
+    /// Allow to downgrade the installed chart to the selected version.
+    /// Downgrades are skipped if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_downgrade: Option<bool>,
+
+    /// Values for the Fleet chart, merged over the values from `valuesFrom`.
+    /// Bootstrap and feature gate settings managed by the controller take precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "helm_values_schema")]
+    pub values: Option<serde_json::Value>,
+
+    /// References to `ConfigMaps` or `Secrets` with values for the Fleet chart, merged in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values_from: Option<Vec<ValuesReference>>,
+}
+
+/// Reference to a `ConfigMap` or `Secret` key with Helm values in YAML format.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuesReference {
+    /// Kind of the values source.
+    pub kind: ValuesSourceKind,
+
+    /// Name of the values source.
+    pub name: String,
+
+    /// Namespace of the values source.
+    pub namespace: String,
+
+    /// Key containing the values. Defaults to `values.yaml`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values_key: Option<String>,
+}
+
+impl ValuesReference {
+    /// Returns the key containing the values.
+    pub(crate) fn values_key(&self) -> &str {
+        self.values_key.as_deref().unwrap_or("values.yaml")
+    }
+}
+
+impl Display for ValuesReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {}/{} key {}",
+            self.kind,
+            self.namespace,
+            self.name,
+            self.values_key()
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub enum ValuesSourceKind {
+    ConfigMap,
+    Secret,
+}
+
+/// Schema for free-form Helm values, which are preserved as is by the API server.
+fn helm_values_schema(_: &mut schemars::r#gen::SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        extensions: [
+            ("nullable".to_string(), true.into()),
+            (
+                "x-kubernetes-preserve-unknown-fields".to_string(),
+                true.into(),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    }
+    .into()
+}
+
+impl FleetInstall {
+    /// Returns the Fleet installation namespace.
+    pub(crate) fn namespace(&self) -> &str {
+        self.namespace.as_deref().unwrap_or(FLEET_NAMESPACE)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Install {
+    /// Follow the latest version of the chart on install
+    FollowLatest(bool),
+
+    /// Use specific version to install
+    Version(String),
+
+    /// Install and upgrade to the highest version matching the semver constraint, e.g. `~0.12`
+    Constraint(String),
+}
+
+impl Install {
+    /// Perform version normalization for comparison with the chart `app_version`
+    pub(crate) fn normalized(self) -> Self {
+        match self {
+            Install::FollowLatest(_) | Install::Constraint(_) => self,
+            Install::Version(version) => {
+                Install::Version(version.strip_prefix("v").unwrap_or(&version).into())
+            }
+        }
+    }
+
+    /// Returns the requirement the installed version has to satisfy.
+    pub(crate) fn requirement(&self) -> Result<VersionReq, semver::Error> {
+        match self.clone().normalized() {
+            Install::FollowLatest(_) => Ok(VersionReq::STAR),
+            Install::Version(version) => VersionReq::parse(&format!("={version}")),
+            Install::Constraint(constraint) => VersionReq::parse(&constraint),
+        }
+    }
+
+    /// Returns whether the installed chart is upgraded to newer matching versions.
+    pub(crate) fn upgrades(&self) -> bool {
+        !matches!(self, Install::FollowLatest(false))
+    }
+}
+
+impl Default for Install {
+    fn default() -> Self {
+        Self::FollowLatest(true)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Server {
+    InferLocal(bool),
+    Custom(InstallOptions),
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallOptions {
+    pub api_server_ca_config_ref: Option<ObjectReference>,
+    pub api_server_url: Option<String>,
+}
+
+impl NamingStrategy {
+    /// Applies prefix and suffix to the name. Names exceeding the 63 character
+    /// DNS label limit are truncated and suffixed with a hash of the full name.
+    #[must_use]
+    pub fn apply(&self, name: Option<String>) -> Option<String> {
+        name.map(|name| match &self.prefix {
+            Some(prefix) => prefix.clone() + &name,
+            None => name,
+        })
+        .map(|name| match &self.suffix {
+            Some(suffix) => name + suffix,
+            None => name,
+        })
+        .map(truncate_name)
+    }
+
+    /// Renders the name from the object metadata using the template if set,
+    /// or the object name otherwise, and applies the strategy to it.
+    /// Templates rendering an empty name fall back to the object name.
+    #[must_use]
+    pub fn render(&self, meta: &ObjectMeta) -> Option<String> {
+        let name = match &self.template {
+            Some(template) => Some(render_template(template, meta))
+                .filter(|name| !name.is_empty())
+                .or_else(|| meta.name.clone()),
+            None => meta.name.clone(),
+        };
+
+        self.apply(name)
+    }
+}
+
+fn render_template(template: &str, meta: &ObjectMeta) -> String {
+    let rendered = render_placeholders(template, |placeholder| meta_placeholder(meta, placeholder));
+
+    // Label values may contain characters not allowed in object names
+    rendered
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '.' => c,
+            _ => '-',
+        })
+        .collect::<String>()
+        .trim_matches(['-', '.'])
+        .to_string()
+}
+
+/// Replaces `{{...}}` placeholders in the template with values returned by the lookup.
+/// Unknown placeholders are rendered as an empty string.
+pub(crate) fn render_placeholders(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some((before, after)) = rest.split_once("{{") {
+        let Some((placeholder, after)) = after.split_once("}}") else {
+            break;
+        };
+        rendered.push_str(before);
+        rendered.push_str(&lookup(placeholder.trim()).unwrap_or_default());
+        rest = after;
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Resolves `name`, `namespace` and `labels.<key>` placeholders from the object metadata.
+pub(crate) fn meta_placeholder(meta: &ObjectMeta, placeholder: &str) -> Option<String> {
+    match placeholder {
+        "name" => meta.name.clone(),
+        "namespace" => meta.namespace.clone(),
+        key => key
+            .strip_prefix("labels.")
+            .and_then(|key| meta.labels.as_ref()?.get(key).cloned()),
+    }
+}
+
+fn truncate_name(name: String) -> String {
+    if name.len() <= MAX_NAME_LENGTH {
+        return name;
+    }
+
+    let hash: String = Sha256::digest(name.as_bytes())
+        .iter()
+        .take(NAME_HASH_BYTES)
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let truncated: String = name
+        .chars()
+        .take(MAX_NAME_LENGTH - hash.len() - 1)
+        .collect();
+
+    format!("{}-{hash}", truncated.trim_end_matches(['-', '.']))
+}
+
+/// Selectors is controlling Fleet import strategy settings.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Selectors {
+    /// Namespace label selector. If set, only clusters in the namespace matching label selector will be imported.
+    pub namespace_selector: LabelSelector,
+
+    /// Cluster label selector. If set, only clusters matching label selector will be imported.
+    pub selector: LabelSelector,
+}
+
+impl FleetAddonConfig {
+    // Raw cluster selector
+    pub(crate) fn cluster_selector(&self) -> Result<Selector, ParseExpressionError> {
+        self.spec
+            .cluster
+            .as_ref()
+            .map(|c| c.selectors.selector.clone())
+            .unwrap_or_default()
+            .try_into()
+    }
+
+    // Raw namespace selector
+    pub(crate) fn namespace_selector(&self) -> Result<Selector, ParseExpressionError> {
+        self.spec
+            .cluster
+            .as_ref()
+            .map(|c| c.selectors.namespace_selector.clone())
+            .unwrap_or_default()
+            .try_into()
+    }
+
+    // Check for general cluster operations, like create, patch, etc. Evaluates to false if disabled.
+    pub(crate) fn cluster_operations_enabled(&self) -> bool {
+        self.spec.cluster.is_some()
+    }
+
+    // Check for general ClusterClass operations, like create, patch, etc. Evaluates to false if disabled.
+    pub(crate) fn cluster_class_operations_enabled(&self) -> bool {
+        self.spec.cluster_class.is_some()
+    }
+
+    // Check for general cluster patching setting.
+    pub(crate) fn cluster_patch_enabled(&self) -> bool {
+        self.spec
+            .cluster
+            .as_ref()
+            .map(|c| c.patch_resource)
+            .unwrap_or_default()
+            .filter(|&enabled| enabled)
+            .is_some()
+    }
+
+    // Check for general clusterClass patching setting.
+    pub(crate) fn cluster_class_patch_enabled(&self) -> bool {
+        self.spec
+            .cluster_class
+            .as_ref()
+            .map(|c| c.patch_resource)
+            .unwrap_or_default()
+            .filter(|&enabled| enabled)
+            .is_some()
+    }
+
+    /// Returns the filter for labels propagated from a `ClusterClass` to the Fleet `ClusterGroups`.
+    pub(crate) fn cluster_class_label_filter(&self) -> Option<&MetadataFilter> {
+        self.spec
+            .cluster_class
+            .as_ref()
+            .and_then(|c| c.label_propagation.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use kube::api::ObjectMeta;
+    use serde_json::json;
+
+    use crate::api::fleet_addon_config::{
+        ClusterConfig, FeatureGates, FleetChartValues, FleetSettingsSpec, KeyPattern,
+        MetadataFilter, NamingStrategy, TemplateSource, TemplateValuesConfig,
+    };
+
+    #[tokio::test]
+    async fn test_naming_strategy() {
+        assert_eq!(
+            Some("prefixtestsuffix".to_string()),
+            NamingStrategy {
+                prefix: "prefix".to_string().into(),
+                suffix: "suffix".to_string().into(),
+                ..Default::default()
+            }
+            .apply("test".to_string().into())
+        );
+
+        assert_eq!(
+            Some("testsuffix".to_string()),
+            NamingStrategy {
+                suffix: "suffix".to_string().into(),
+                ..Default::default()
+            }
+            .apply("test".to_string().into())
+        );
+
+        assert_eq!(
+            Some("prefixtest".to_string()),
+            NamingStrategy {
+                prefix: "prefix".to_string().into(),
+                ..Default::default()
+            }
+            .apply("test".to_string().into())
+        );
+
+        assert_eq!(
+            Some("test".to_string()),
+            NamingStrategy {
+                ..Default::default()
+            }
+            .apply("test".to_string().into())
+        );
+
+        assert_eq!(
+            None,
+            NamingStrategy {
+                prefix: "prefix".to_string().into(),
+                suffix: "suffix".to_string().into(),
+                ..Default::default()
+            }
+            .apply(None)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_propagated_metadata() {
+        let labels = BTreeMap::from([
+            ("env".to_string(), "prod".to_string()),
+            ("team.example.com/owner".to_string(), "a".to_string()),
+            ("topology.cluster.x-k8s.io/owned".to_string(), String::new()),
+        ]);
+        let config = ClusterConfig {
+            label_propagation: Some(MetadataFilter {
+                include: vec![
+                    KeyPattern::Prefix("team.example.com/".into()),
+                    KeyPattern::Regex("^(env|topology\\..*)$".into()),
+                ],
+                exclude: vec![KeyPattern::Prefix("topology.cluster.x-k8s.io/".into())],
+            }),
+            annotation_propagation: Some(MetadataFilter {
+                exclude: vec![KeyPattern::Prefix("kubectl.kubernetes.io/".into())],
+                ..Default::default()
+            }),
+            extra_labels: Some(BTreeMap::from([("env".to_string(), "static".to_string())])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.propagated_labels(labels),
+            BTreeMap::from([
+                ("env".to_string(), "static".to_string()),
+                ("team.example.com/owner".to_string(), "a".to_string()),
+            ])
+        );
+
+        let annotations = BTreeMap::from([
+            (
+                "kubectl.kubernetes.io/last-applied-configuration".to_string(),
+                "{}".to_string(),
+            ),
+            ("note".to_string(), "kept".to_string()),
+        ]);
+        assert_eq!(
+            config.propagated_annotations(annotations),
+            BTreeMap::from([("note".to_string(), "kept".to_string())])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_naming_template() {
+        let meta = ObjectMeta {
+            name: Some("cluster".into()),
+            namespace: Some("tenant".into()),
+            labels: Some(BTreeMap::from([("env".to_string(), "Prod_1".to_string())])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some("tenant-cluster".to_string()),
+            NamingStrategy {
+                template: Some("{{namespace}}-{{name}}".into()),
+                ..Default::default()
+            }
+            .render(&meta)
+        );
+
+        assert_eq!(
+            Some("caapf-prod-1-cluster".to_string()),
+            NamingStrategy {
+                prefix: Some("caapf-".into()),
+                template: Some("{{ labels.env }}-{{name}}-{{labels.missing}}".into()),
+                ..Default::default()
+            }
+            .render(&meta)
+        );
+
+        assert_eq!(
+            Some("cluster".to_string()),
+            NamingStrategy::default().render(&meta)
+        );
+
+        assert_eq!(
+            Some("caapf-cluster".to_string()),
+            NamingStrategy {
+                prefix: Some("caapf-".into()),
+                template: Some("{{labels.missing}}-{{labels.other}}".into()),
+                ..Default::default()
+            }
+            .render(&meta)
+        );
+
+        let long = NamingStrategy::default()
+            .apply(Some("a".repeat(70)))
+            .unwrap();
+        assert_eq!(long.len(), 63);
+        assert_eq!(
+            long,
+            NamingStrategy::default()
+                .apply(Some("a".repeat(70)))
+                .unwrap()
+        );
+        assert_ne!(
+            long,
+            NamingStrategy::default()
+                .apply(Some("a".repeat(71)))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_template_source_selector() {
+        let source = TemplateSource {
+            key: "MachineDeployments".into(),
+            api_version: "cluster.x-k8s.io/v1beta1".into(),
+            kind: "MachineDeployment".into(),
+            selector: Some(LabelSelector {
+                match_labels: Some(BTreeMap::from([(
+                    "cluster.x-k8s.io/cluster-name".to_string(),
+                    "{{name}}".to_string(),
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let selector = source
+            .render_selector(|placeholder| (placeholder == "name").then(|| "dev".to_string()))
+            .unwrap();
+        assert_eq!(selector.to_string(), "cluster.x-k8s.io/cluster-name=dev");
+
+        let everything = TemplateSource::default().render_selector(|_| None).unwrap();
+        assert!(everything.selects_all());
+    }
+
+    #[test]
+    fn test_template_values_select() {
+        let config = TemplateValuesConfig {
+            fields: BTreeMap::from([(
+                "ControlPlane".to_string(),
+                vec![
+                    "spec.version".to_string(),
+                    "metadata.labels.cluster\\.x-k8s\\.io/cluster-name".to_string(),
+                    "spec.machineTemplate.missing".to_string(),
+                    "spec.files.path".to_string(),
+                ],
+            )]),
+            ..Default::default()
+        };
+
+        let control_plane = json!({
+            "metadata": {"name": "cp", "labels": {"cluster.x-k8s.io/cluster-name": "dev", "other": "x"}},
+            "spec": {
+                "version": "v1.31.0",
+                "replicas": 3,
+                "files": [{"path": "/a", "content": "a"}, {"content": "b"}, {"path": "/c"}],
+            },
+        });
+
+        assert_eq!(
+            config.select("ControlPlane", control_plane.clone()),
+            json!({
+                "metadata": {"labels": {"cluster.x-k8s.io/cluster-name": "dev"}},
+                "spec": {"version": "v1.31.0", "files": [{"path": "/a"}, null, {"path": "/c"}]},
+            })
+        );
+        assert_eq!(
+            config.select("Cluster", control_plane.clone()),
+            control_plane
+        );
+        assert_eq!(config.max_size(), 512 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_sync_config_map() {
+        let want_fleet_data = r"extraEnv:
+- name: EXPERIMENTAL_HELM_OPS
+  value: 'true'
+- name: foo
+  value: bar
+- name: EXPERIMENTAL_OCI_STORAGE
+  value: 'true'
+foo:
+  bar: foobar
+";
+        let fleet_data = r#"extraEnv:
+- name: EXPERIMENTAL_HELM_OPS
+  value: "false"
+- name: foo
+  value: bar
+foo:
+  bar: foobar
+"#;
+        let mut data = FleetSettingsSpec {
+            fleet: FleetChartValues::from_str(fleet_data).unwrap(),
+            ..Default::default()
+        };
+        let feature_gates = FeatureGates::default();
+
+        feature_gates.merge_features(&mut data);
+
+        assert_eq!(want_fleet_data.to_string(), data.fleet.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_sync_empty_config_map() {
+        let want_fleet_data = r"extraEnv:
+- name: EXPERIMENTAL_HELM_OPS
+  value: 'false'
+- name: EXPERIMENTAL_OCI_STORAGE
+  value: 'false'
+";
+        let mut data = FleetSettingsSpec::default();
+        let feature_gates = FeatureGates {
+            experimental_oci_storage: false,
+            experimental_helm_ops: false,
+            config_map: None,
+        };
+
+        feature_gates.merge_features(&mut data);
+
+        assert_eq!(want_fleet_data.to_string(), data.fleet.to_string());
+    }
+}
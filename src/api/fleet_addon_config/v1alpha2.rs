@@ -0,0 +1,701 @@
+use std::collections::BTreeMap;
+
+use fleet_api_rs::fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations};
+use k8s_openapi::{
+    api::core::v1::ObjectReference, apimachinery::pkg::apis::meta::v1::LabelSelector,
+};
+use kube::core::Rule;
+use kube::{CustomResource, KubeSchema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "agent-initiated")]
+use super::AgentBootstrap;
+use super::{
+    ClusterGroupRule, FeatureGates, FleetAddonConfigStatus, GarbageCollection, MetadataFilter,
+    NamingStrategy, ReadinessGate, TemplateSource, TemplateValuesConfig, ValuesReference,
+};
+
+/// This provides a config for fleet addon functionality
+#[derive(CustomResource, Deserialize, Serialize, Clone, Default, Debug, KubeSchema, PartialEq)]
+#[kube(
+    kind = "FleetAddonConfig",
+    group = "addons.cluster.x-k8s.io",
+    version = "v1alpha2",
+    status = "FleetAddonConfigStatus",
+    validation = "self.metadata.name == 'fleet-addon-config'"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAddonConfigSpec {
+    /// Enable clusterClass controller functionality.
+    ///
+    /// This will create Fleet `ClusterGroups` for each `ClusterClass` with the same name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_class: Option<ClusterClassConfig>,
+
+    /// Enable Cluster config functionality.
+    ///
+    /// This will create Fleet Cluster for each Cluster with the same name.
+    /// In case the cluster specifies topology.class, the name of the `ClusterClass`
+    /// will be added to the Fleet Cluster labels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<ClusterConfig>,
+
+    /// Fleet chart configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<FleetConfig>,
+
+    /// Fleet chart installation options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install: Option<FleetInstall>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterClassConfig {
+    /// Set owner references on the created resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_owner_references: Option<bool>,
+
+    /// Allow to patch resources, maintaining the desired state.
+    /// If disabled, resources will only be re-created in case of removal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_resource: Option<bool>,
+
+    /// Periodic removal of `ClusterGroups` and `BundleNamespaceMappings` left behind
+    /// by removed `ClusterClasses` or clusters. Disabled if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub garbage_collection: Option<GarbageCollection>,
+
+    /// Filter for labels propagated from the `ClusterClass` to the Fleet `ClusterGroups`.
+    /// If not set, all labels are propagated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_propagation: Option<MetadataFilter>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfig {
+    /// Apply a `ClusterGroup` for a `ClusterClass` referenced from a different namespace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apply_class_group: Option<bool>,
+
+    /// Allow to patch resources, maintaining the desired state.
+    /// If disabled, resources will only be re-created in case of removal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_resource: Option<bool>,
+
+    /// Set owner references on the created resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_owner_references: Option<bool>,
+
+    /// Naming settings for the fleet cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub naming: Option<NamingStrategy>,
+
+    /// Fleet agent settings applied to every imported cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentConfig>,
+
+    /// Filter for labels propagated from the CAPI cluster to the Fleet cluster.
+    /// If not set, all labels are propagated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_propagation: Option<MetadataFilter>,
+
+    /// Filter for annotations propagated from the CAPI cluster to the Fleet cluster.
+    /// If not set, all annotations are propagated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<MetadataFilter>,
+
+    /// Static labels added to every Fleet cluster, overriding propagated labels with the same key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_labels: Option<BTreeMap<String, String>>,
+
+    /// Additional objects exposed in the Fleet cluster template values under their keys,
+    /// next to `Cluster`, `ControlPlane` and `InfrastructureCluster`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_sources: Option<Vec<TemplateSource>>,
+
+    /// Field allowlist and size limit for the Fleet cluster template values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_values: Option<TemplateValuesConfig>,
+
+    /// Conditions required on the CAPI cluster before it is imported, in addition to
+    /// the initialized control plane.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_gates: Option<Vec<ReadinessGate>>,
+
+    /// Rules creating Fleet `ClusterGroups` for CAPI clusters matching a label selector,
+    /// independently of the `ClusterClass` topology.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_groups: Option<Vec<ClusterGroupRule>>,
+
+    /// Import settings for the CAPI cluster. Allows to import clusters based on a set of labels,
+    /// set on the cluster or the namespace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selectors: Option<Selectors>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// Prepare initial cluster for agent initiated connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_initiated: Option<bool>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// TTL of the `ClusterRegistrationToken` created for agent initiated connection,
+    /// as a duration string like `24h`. Defaults to `1h`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_token_ttl: Option<String>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// Delivery of the Fleet agent into the workload cluster for agent initiated connection.
+    /// If not set, the agent has to be installed manually.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_bootstrap: Option<AgentBootstrap>,
+}
+
+/// `AgentConfig` is controlling Fleet agent deployment settings.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentConfig {
+    /// Namespace selection for the fleet agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Agent taint toleration settings for every cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tolerations: Option<Vec<ClusterAgentTolerations>>,
+
+    /// Host network allows to deploy agent configuration using hostNetwork: true setting
+    /// which eludes dependency on the CNI configuration for the cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_network: Option<bool>,
+
+    /// Extra environment variables to be added to the agent deployment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_vars: Option<Vec<ClusterAgentEnvVars>>,
+}
+
+/// Selectors is controlling Fleet import strategy settings.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Selectors {
+    /// Namespace label selector. If set, only clusters in the namespace matching label selector will be imported.
+    #[serde(default)]
+    pub namespace_selector: LabelSelector,
+
+    /// Cluster label selector. If set, only clusters matching label selector will be imported.
+    #[serde(default)]
+    pub selector: LabelSelector,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetConfig {
+    /// fleet server url configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<Server>,
+
+    /// feature gates controlling experimental features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature_gates: Option<FeatureGates>,
+
+    /// Enable auto-installation of a fleet agent in the local cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootstrap_local_cluster: Option<bool>,
+}
+
+/// `Server` is controlling the fleet server url and CA settings.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, KubeSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+// The stored v1alpha1 version holds either the local inference or the custom settings
+#[x_kube(
+    validation = Rule::new(
+        "!(has(self.inferLocal) && self.inferLocal) || (!has(self.apiServerUrl) && !has(self.apiServerCaConfigRef))"
+    ).message("inferLocal can't be combined with apiServerUrl or apiServerCaConfigRef")
+)]
+pub struct Server {
+    /// Use the default `kubernetes` endpoint and `kube-root-ca.crt` as the fleet server
+    /// url and CA. Can't be combined with the custom url and CA reference.
+    #[serde(default)]
+    pub infer_local: bool,
+
+    /// Custom fleet server url
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_server_url: Option<String>,
+
+    /// Reference to a `ConfigMap` containing the `ca.crt` key for the fleet server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_server_ca_config_ref: Option<ObjectReference>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, KubeSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+// The stored v1alpha1 version holds one of the version, constraint or followLatest
+#[x_kube(
+    validation = Rule::new(
+        "[has(self.version), has(self.constraint), has(self.followLatest) && self.followLatest].filter(set, set).size() <= 1"
+    ).message("Only one of version, constraint and followLatest can be set")
+)]
+pub struct FleetInstall {
+    /// Chart version to install. Latest available version is installed if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Upgrade the installed chart to the latest available version.
+    /// Can't be combined with the version or constraint.
+    #[serde(default)]
+    pub follow_latest: bool,
+
+    /// Install and upgrade to the highest version matching the semver constraint, e.g. `~0.12`.
+    /// Can't be combined with the version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+
+    /// Allow pre-release versions to satisfy the version constraint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_prerelease: Option<bool>,
+
+    /// Allow to downgrade the installed chart to the selected version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_downgrade: Option<bool>,
+
+    /// Helm repository URL to install the Fleet charts from.
+    /// Defaults to the upstream Fleet Helm repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+
+    /// OCI reference hosting the `fleet` and `fleet-crd` charts, e.g. `oci://registry.example.com/charts`.
+    /// Takes precedence over the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oci_repository: Option<String>,
+
+    /// Reference to a secret with `username` and `password` keys, used to authenticate
+    /// with the Helm repository or OCI registry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credentials_secret_ref: Option<ObjectReference>,
+
+    /// Reference to a config map with the `ca.crt` key, containing the CA bundle
+    /// used to verify the Helm repository or OCI registry certificate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_ref: Option<ObjectReference>,
+
+    /// Namespace to install Fleet into. Defaults to `cattle-fleet-system`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Values for the Fleet chart, merged over the values from `valuesFrom`.
+    /// Bootstrap and feature gate settings managed by the controller take precedence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "super::helm_values_schema")]
+    pub values: Option<serde_json::Value>,
+
+    /// References to `ConfigMaps` or `Secrets` with values for the Fleet chart, merged in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values_from: Option<Vec<ValuesReference>>,
+}
+
+impl From<super::FleetAddonConfig> for FleetAddonConfig {
+    fn from(config: super::FleetAddonConfig) -> Self {
+        Self {
+            metadata: config.metadata,
+            spec: config.spec.into(),
+            status: config.status,
+        }
+    }
+}
+
+impl From<FleetAddonConfig> for super::FleetAddonConfig {
+    fn from(config: FleetAddonConfig) -> Self {
+        Self {
+            metadata: config.metadata,
+            spec: config.spec.into(),
+            status: config.status,
+        }
+    }
+}
+
+impl From<super::FleetAddonConfigSpec> for FleetAddonConfigSpec {
+    fn from(spec: super::FleetAddonConfigSpec) -> Self {
+        Self {
+            cluster_class: spec.cluster_class.map(Into::into),
+            cluster: spec.cluster.map(Into::into),
+            config: spec.config.map(Into::into),
+            install: spec.install.map(Into::into),
+        }
+    }
+}
+
+impl From<FleetAddonConfigSpec> for super::FleetAddonConfigSpec {
+    fn from(spec: FleetAddonConfigSpec) -> Self {
+        Self {
+            cluster_class: spec.cluster_class.map(Into::into),
+            cluster: spec.cluster.map(Into::into),
+            config: spec.config.map(Into::into),
+            install: spec.install.map(Into::into),
+        }
+    }
+}
+
+impl From<super::ClusterClassConfig> for ClusterClassConfig {
+    fn from(config: super::ClusterClassConfig) -> Self {
+        Self {
+            set_owner_references: config.set_owner_references,
+            patch_resource: config.patch_resource,
+            garbage_collection: config.garbage_collection,
+            label_propagation: config.label_propagation,
+        }
+    }
+}
+
+impl From<ClusterClassConfig> for super::ClusterClassConfig {
+    fn from(config: ClusterClassConfig) -> Self {
+        Self {
+            set_owner_references: config.set_owner_references,
+            patch_resource: config.patch_resource,
+            garbage_collection: config.garbage_collection,
+            label_propagation: config.label_propagation,
+        }
+    }
+}
+
+impl From<super::ClusterConfig> for ClusterConfig {
+    fn from(config: super::ClusterConfig) -> Self {
+        let agent = AgentConfig {
+            namespace: config.agent_namespace,
+            tolerations: config.agent_tolerations,
+            host_network: config.host_network,
+            env_vars: config.agent_env_vars,
+        };
+        let selectors = Selectors {
+            namespace_selector: config.selectors.namespace_selector,
+            selector: config.selectors.selector,
+        };
+
+        Self {
+            apply_class_group: config.apply_class_group,
+            patch_resource: config.patch_resource,
+            set_owner_references: config.set_owner_references,
+            naming: config.naming,
+            agent: (agent != AgentConfig::default()).then_some(agent),
+            label_propagation: config.label_propagation,
+            annotation_propagation: config.annotation_propagation,
+            extra_labels: config.extra_labels,
+            template_sources: config.template_sources,
+            template_values: config.template_values,
+            readiness_gates: config.readiness_gates,
+            cluster_groups: config.cluster_groups,
+            selectors: (selectors != Selectors::default()).then_some(selectors),
+            #[cfg(feature = "agent-initiated")]
+            agent_initiated: config.agent_initiated,
+            #[cfg(feature = "agent-initiated")]
+            registration_token_ttl: config.registration_token_ttl,
+            #[cfg(feature = "agent-initiated")]
+            agent_bootstrap: config.agent_bootstrap,
+        }
+    }
+}
+
+impl From<ClusterConfig> for super::ClusterConfig {
+    fn from(config: ClusterConfig) -> Self {
+        let agent = config.agent.unwrap_or_default();
+        let selectors = config.selectors.unwrap_or_default();
+
+        Self {
+            apply_class_group: config.apply_class_group,
+            patch_resource: config.patch_resource,
+            set_owner_references: config.set_owner_references,
+            naming: config.naming,
+            agent_namespace: agent.namespace,
+            agent_tolerations: agent.tolerations,
+            host_network: agent.host_network,
+            agent_env_vars: agent.env_vars,
+            label_propagation: config.label_propagation,
+            annotation_propagation: config.annotation_propagation,
+            extra_labels: config.extra_labels,
+            template_sources: config.template_sources,
+            template_values: config.template_values,
+            readiness_gates: config.readiness_gates,
+            cluster_groups: config.cluster_groups,
+            selectors: super::Selectors {
+                namespace_selector: selectors.namespace_selector,
+                selector: selectors.selector,
+            },
+            #[cfg(feature = "agent-initiated")]
+            agent_initiated: config.agent_initiated,
+            #[cfg(feature = "agent-initiated")]
+            registration_token_ttl: config.registration_token_ttl,
+            #[cfg(feature = "agent-initiated")]
+            agent_bootstrap: config.agent_bootstrap,
+        }
+    }
+}
+
+impl From<super::FleetConfig> for FleetConfig {
+    fn from(config: super::FleetConfig) -> Self {
+        Self {
+            server: config.server.map(Into::into),
+            feature_gates: config.feature_gates,
+            bootstrap_local_cluster: config.bootstrap_local_cluster,
+        }
+    }
+}
+
+impl From<FleetConfig> for super::FleetConfig {
+    fn from(config: FleetConfig) -> Self {
+        Self {
+            server: config.server.map(Into::into),
+            feature_gates: config.feature_gates,
+            bootstrap_local_cluster: config.bootstrap_local_cluster,
+        }
+    }
+}
+
+impl From<super::Server> for Server {
+    fn from(server: super::Server) -> Self {
+        match server {
+            super::Server::InferLocal(infer_local) => Self {
+                infer_local,
+                ..Default::default()
+            },
+            super::Server::Custom(options) => Self {
+                infer_local: false,
+                api_server_url: options.api_server_url,
+                api_server_ca_config_ref: options.api_server_ca_config_ref,
+            },
+        }
+    }
+}
+
+impl From<Server> for super::Server {
+    fn from(server: Server) -> Self {
+        match server {
+            Server {
+                infer_local: false,
+                api_server_url: None,
+                api_server_ca_config_ref: None,
+            } => Self::InferLocal(false),
+            Server {
+                infer_local: true, ..
+            } => Self::InferLocal(true),
+            Server {
+                api_server_url,
+                api_server_ca_config_ref,
+                ..
+            } => Self::Custom(super::InstallOptions {
+                api_server_ca_config_ref,
+                api_server_url,
+            }),
+        }
+    }
+}
+
+impl From<super::FleetInstall> for FleetInstall {
+    fn from(install: super::FleetInstall) -> Self {
+        let (version, constraint, follow_latest) = match install.install_version {
+            super::Install::FollowLatest(follow_latest) => (None, None, follow_latest),
+            super::Install::Version(version) => (Some(version), None, false),
+            super::Install::Constraint(constraint) => (None, Some(constraint), false),
+        };
+        Self {
+            version,
+            follow_latest,
+            constraint,
+            allow_prerelease: install.allow_prerelease,
+            allow_downgrade: install.allow_downgrade,
+            repository: install.repository,
+            oci_repository: install.oci_repository,
+            credentials_secret_ref: install.credentials_secret_ref,
+            ca_bundle_ref: install.ca_bundle_ref,
+            namespace: install.namespace,
+            values: install.values,
+            values_from: install.values_from,
+        }
+    }
+}
+
+impl From<FleetInstall> for super::FleetInstall {
+    fn from(install: FleetInstall) -> Self {
+        Self {
+            install_version: match (install.version, install.constraint) {
+                (Some(version), _) => super::Install::Version(version),
+                (None, Some(constraint)) => super::Install::Constraint(constraint),
+                (None, None) => super::Install::FollowLatest(install.follow_latest),
+            },
+            allow_prerelease: install.allow_prerelease,
+            allow_downgrade: install.allow_downgrade,
+            repository: install.repository,
+            oci_repository: install.oci_repository,
+            credentials_secret_ref: install.credentials_secret_ref,
+            ca_bundle_ref: install.ca_bundle_ref,
+            namespace: install.namespace,
+            values: install.values,
+            values_from: install.values_from,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::{
+        api::core::v1::ObjectReference, apimachinery::pkg::apis::meta::v1::LabelSelector,
+    };
+    use kube::CustomResourceExt as _;
+    use kube::api::ObjectMeta;
+
+    use crate::api::fleet_addon_config::{
+        self as v1alpha1, FeatureGates, Install, InstallOptions, NamingStrategy,
+    };
+
+    use super::FleetAddonConfig;
+
+    #[test]
+    fn test_round_trip_conversion() {
+        let config = v1alpha1::FleetAddonConfig {
+            metadata: ObjectMeta {
+                name: Some("fleet-addon-config".into()),
+                ..Default::default()
+            },
+            spec: v1alpha1::FleetAddonConfigSpec {
+                cluster_class: Some(v1alpha1::ClusterClassConfig::default()),
+                cluster: Some(v1alpha1::ClusterConfig {
+                    naming: Some(NamingStrategy {
+                        prefix: Some("prefix-".into()),
+                        ..Default::default()
+                    }),
+                    selectors: v1alpha1::Selectors {
+                        selector: LabelSelector {
+                            match_labels: Some(BTreeMap::from([(
+                                "import".to_string(),
+                                "true".to_string(),
+                            )])),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+                config: Some(v1alpha1::FleetConfig {
+                    server: Some(v1alpha1::Server::Custom(InstallOptions {
+                        api_server_url: Some("https://fleet.example.com".into()),
+                        api_server_ca_config_ref: Some(ObjectReference {
+                            name: Some("ca".into()),
+                            ..Default::default()
+                        }),
+                    })),
+                    feature_gates: Some(FeatureGates::default()),
+                    bootstrap_local_cluster: Some(true),
+                }),
+                install: Some(v1alpha1::FleetInstall {
+                    install_version: Install::Version("v0.12.0".into()),
+                    oci_repository: Some("oci://registry.example.com/charts".into()),
+                    credentials_secret_ref: Some(ObjectReference {
+                        name: Some("registry-credentials".into()),
+                        ..Default::default()
+                    }),
+                    namespace: Some("fleet-system".into()),
+                    allow_prerelease: Some(true),
+                    allow_downgrade: Some(false),
+                    ..Default::default()
+                }),
+            },
+            status: None,
+        };
+
+        let converted: FleetAddonConfig = config.clone().into();
+        let selectors = converted.spec.cluster.as_ref().unwrap().selectors.as_ref();
+        assert_eq!(
+            selectors.unwrap().selector.match_labels,
+            Some(BTreeMap::from([("import".to_string(), "true".to_string())]))
+        );
+        assert_eq!(
+            converted.spec.install.as_ref().unwrap().version.as_deref(),
+            Some("v0.12.0")
+        );
+
+        let round_trip: v1alpha1::FleetAddonConfig = converted.into();
+        assert_eq!(round_trip.spec, config.spec);
+    }
+
+    #[test]
+    fn test_unset_round_trip_conversion() {
+        let spec = v1alpha1::FleetAddonConfigSpec {
+            cluster_class: Some(v1alpha1::ClusterClassConfig {
+                patch_resource: None,
+                set_owner_references: None,
+                ..Default::default()
+            }),
+            cluster: Some(v1alpha1::ClusterConfig {
+                apply_class_group: None,
+                patch_resource: None,
+                set_owner_references: None,
+                host_network: None,
+                ..Default::default()
+            }),
+            config: Some(v1alpha1::FleetConfig::default()),
+            install: Some(v1alpha1::FleetInstall::default()),
+        };
+
+        let converted: super::FleetAddonConfigSpec = spec.clone().into();
+        let cluster_class = converted.cluster_class.as_ref().unwrap();
+        assert_eq!(cluster_class.patch_resource, None);
+        assert_eq!(cluster_class.set_owner_references, None);
+
+        let round_trip: v1alpha1::FleetAddonConfigSpec = converted.into();
+        assert_eq!(round_trip, spec);
+    }
+
+    #[test]
+    fn test_server_conversion() {
+        for server in [
+            v1alpha1::Server::InferLocal(true),
+            v1alpha1::Server::InferLocal(false),
+            v1alpha1::Server::Custom(InstallOptions {
+                api_server_url: Some("https://fleet.example.com".into()),
+                api_server_ca_config_ref: Some(ObjectReference {
+                    name: Some("fleet-ca".into()),
+                    ..Default::default()
+                }),
+            }),
+        ] {
+            let converted: super::Server = server.clone().into();
+            let round_trip: v1alpha1::Server = converted.clone().into();
+            assert_eq!(round_trip, server);
+            assert_eq!(super::Server::from(round_trip), converted);
+        }
+    }
+
+    #[test]
+    fn test_install_conversion() {
+        for install_version in [
+            Install::FollowLatest(true),
+            Install::FollowLatest(false),
+            Install::Version("v0.12.0".into()),
+            Install::Constraint("~0.12".into()),
+        ] {
+            let install = v1alpha1::FleetInstall {
+                install_version,
+                ..Default::default()
+            };
+            let converted: super::FleetInstall = install.clone().into();
+            let round_trip: v1alpha1::FleetInstall = converted.clone().into();
+            assert_eq!(round_trip, install);
+            assert_eq!(super::FleetInstall::from(round_trip), converted);
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_settings_rejected() {
+        let crd = serde_json::to_value(FleetAddonConfig::crd()).unwrap();
+        let spec = &crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"];
+        let server = &spec["properties"]["config"]["properties"]["server"];
+        let install = &spec["properties"]["install"];
+        for schema in [server, install] {
+            assert_eq!(
+                schema["x-kubernetes-validations"].as_array().map(Vec::len),
+                Some(1)
+            );
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use fleet_api_rs::fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations};
+use kube::{CustomResource, KubeSchema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use super::fleet_addon_config::{
+    ClusterConfig, ClusterGroupRule, MetadataFilter, NamingStrategy, ReadinessGate,
+    TemplateValuesConfig,
+};
+
+/// Name of the `FleetAddonConfigOverride` object, considered in each namespace.
+pub const OVERRIDE_NAME: &str = "fleet-addon-config";
+
+/// This provides namespace level overrides for the global `FleetAddonConfig` cluster settings.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Default, Debug, KubeSchema, PartialEq)]
+#[kube(
+    kind = "FleetAddonConfigOverride",
+    group = "addons.cluster.x-k8s.io",
+    version = "v1alpha1",
+    namespaced,
+    validation = "self.metadata.name == 'fleet-addon-config'"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAddonConfigOverrideSpec {
+    /// Cluster settings overriding the global `FleetAddonConfig` for every Cluster in the namespace.
+    #[serde(default)]
+    pub cluster: ClusterConfigOverride,
+}
+
+/// `ClusterConfigOverride` holds `ClusterConfig` settings which are applied over the global config when set.
+///
+/// Every `ClusterConfig` setting can be overridden, except the import selectors, which select
+/// the namespaces watched by the controller, and the template sources and agent bootstrap,
+/// which are read with the controller permissions in any namespace. These stay global.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfigOverride {
+    /// Apply a `ClusterGroup` for a `ClusterClass` referenced from a different namespace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apply_class_group: Option<bool>,
+
+    /// Allow to patch resources, maintaining the desired state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_resource: Option<bool>,
+
+    /// Setting to disable setting owner references on the created resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_owner_references: Option<bool>,
+
+    /// Naming settings for the fleet cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub naming: Option<NamingStrategy>,
+
+    /// Namespace selection for the fleet agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_namespace: Option<String>,
+
+    /// Agent taint toleration settings for every cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_tolerations: Option<Vec<ClusterAgentTolerations>>,
+
+    /// Host network allows to deploy agent configuration using hostNetwork: true setting
+    /// which eludes dependency on the CNI configuration for the cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_network: Option<bool>,
+
+    /// `AgentEnvVars` are extra environment variables to be added to the agent deployment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_env_vars: Option<Vec<ClusterAgentEnvVars>>,
+
+    /// Filter for labels propagated from the CAPI cluster to the Fleet cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_propagation: Option<MetadataFilter>,
+
+    /// Filter for annotations propagated from the CAPI cluster to the Fleet cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<MetadataFilter>,
+
+    /// Static labels added to every Fleet cluster, overriding propagated labels with the same key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_labels: Option<BTreeMap<String, String>>,
+
+    /// Field allowlist and size limit for the Fleet cluster template values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_values: Option<TemplateValuesConfig>,
+
+    /// Conditions required on the CAPI cluster before it is imported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_gates: Option<Vec<ReadinessGate>>,
+
+    /// Rules creating Fleet `ClusterGroups` for CAPI clusters matching a label selector.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_groups: Option<Vec<ClusterGroupRule>>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// Prepare initial cluster for agent initiated connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_initiated: Option<bool>,
+
+    #[cfg(feature = "agent-initiated")]
+    /// TTL of the `ClusterRegistrationToken` created for agent initiated connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_token_ttl: Option<String>,
+}
+
+impl ClusterConfigOverride {
+    /// Applies every set override to the global cluster config.
+    pub(crate) fn apply(&self, config: &mut ClusterConfig) {
+        let overrides = self.clone();
+        config.apply_class_group = overrides.apply_class_group.or(config.apply_class_group);
+        config.patch_resource = overrides.patch_resource.or(config.patch_resource);
+        config.set_owner_references = overrides
+            .set_owner_references
+            .or(config.set_owner_references);
+        config.naming = overrides.naming.or(config.naming.take());
+        config.agent_namespace = overrides.agent_namespace.or(config.agent_namespace.take());
+        config.agent_tolerations = overrides
+            .agent_tolerations
+            .or(config.agent_tolerations.take());
+        config.host_network = overrides.host_network.or(config.host_network);
+        config.agent_env_vars = overrides.agent_env_vars.or(config.agent_env_vars.take());
+        config.label_propagation = overrides
+            .label_propagation
+            .or(config.label_propagation.take());
+        config.annotation_propagation = overrides
+            .annotation_propagation
+            .or(config.annotation_propagation.take());
+        config.extra_labels = overrides.extra_labels.or(config.extra_labels.take());
+        config.template_values = overrides.template_values.or(config.template_values.take());
+        config.readiness_gates = overrides.readiness_gates.or(config.readiness_gates.take());
+        config.cluster_groups = overrides.cluster_groups.or(config.cluster_groups.take());
+        #[cfg(feature = "agent-initiated")]
+        {
+            config.agent_initiated = overrides.agent_initiated.or(config.agent_initiated);
+            config.registration_token_ttl = overrides
+                .registration_token_ttl
+                .or(config.registration_token_ttl.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+
+    #[cfg(feature = "agent-initiated")]
+    use crate::api::fleet_addon_config::AgentBootstrap;
+    use crate::api::fleet_addon_config::{
+        ClusterConfig, ClusterGroupRule, MetadataFilter, NamingStrategy, ReadinessGate, Selectors,
+        TemplateSource, TemplateValuesConfig,
+    };
+
+    use super::ClusterConfigOverride;
+
+    /// Lists every `ClusterConfig` field, so a new field fails to compile until it is
+    /// added to the override, or excluded here like the import selectors and template sources.
+    #[test]
+    fn test_override_mirrors_cluster_config() {
+        let config = ClusterConfig {
+            apply_class_group: Some(false),
+            patch_resource: Some(false),
+            set_owner_references: Some(false),
+            naming: Some(NamingStrategy {
+                prefix: Some("tenant-".into()),
+                ..Default::default()
+            }),
+            agent_namespace: Some("tenant-agent".into()),
+            agent_tolerations: Some(vec![]),
+            host_network: Some(false),
+            agent_env_vars: Some(vec![]),
+            label_propagation: Some(MetadataFilter::default()),
+            annotation_propagation: Some(MetadataFilter::default()),
+            extra_labels: Some(BTreeMap::from([("tenant".into(), "a".into())])),
+            template_sources: Some(vec![TemplateSource {
+                key: "Values".into(),
+                api_version: "v1".into(),
+                kind: "ConfigMap".into(),
+                name: Some("values".into()),
+                ..Default::default()
+            }]),
+            template_values: Some(TemplateValuesConfig::default()),
+            readiness_gates: Some(vec![ReadinessGate {
+                condition_type: "Ready".into(),
+                status: None,
+            }]),
+            cluster_groups: Some(vec![ClusterGroupRule {
+                name: "edge".into(),
+                selector: LabelSelector::default(),
+            }]),
+            selectors: Selectors::default(),
+            #[cfg(feature = "agent-initiated")]
+            agent_initiated: Some(false),
+            #[cfg(feature = "agent-initiated")]
+            registration_token_ttl: Some("24h".into()),
+            #[cfg(feature = "agent-initiated")]
+            agent_bootstrap: Some(AgentBootstrap::default()),
+        };
+
+        let mut value = serde_json::to_value(&config).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("namespaceSelector");
+        fields.remove("selector");
+
+        let config_override: ClusterConfigOverride = serde_json::from_value(value).unwrap();
+        let mut applied = ClusterConfig::default();
+        config_override.apply(&mut applied);
+        assert_eq!(
+            applied,
+            ClusterConfig {
+                template_sources: None,
+                #[cfg(feature = "agent-initiated")]
+                agent_bootstrap: None,
+                ..config
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_override() {
+        let mut config = ClusterConfig {
+            naming: Some(NamingStrategy {
+                prefix: Some("global-".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        ClusterConfigOverride {
+            agent_namespace: Some("tenant-agent".into()),
+            host_network: Some(false),
+            ..Default::default()
+        }
+        .apply(&mut config);
+
+        assert_eq!(config.agent_namespace, Some("tenant-agent".into()));
+        assert_eq!(config.host_network, Some(false));
+        assert_eq!(config.apply_class_group, Some(true));
+        assert_eq!(
+            config.naming.and_then(|naming| naming.prefix),
+            Some("global-".into())
+        );
+    }
+}
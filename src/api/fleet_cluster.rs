@@ -6,7 +6,13 @@ use kube::{
 use serde::{Deserialize, Serialize};
 
 use crate::api::comparable::ResourceDiff;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+
+/// Bookkeeping annotation listing label keys propagated to the Fleet cluster or cluster group.
+pub static PROPAGATED_LABELS_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/propagated-labels";
+/// Bookkeeping annotation listing annotation keys propagated to the Fleet cluster.
+pub static PROPAGATED_ANNOTATIONS_ANNOTATION: &str =
+    "fleet.addons.cluster.x-k8s.io/propagated-annotations";
 
 #[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[resource(inherit = fleet_api_rs::fleet_cluster::Cluster)]
@@ -18,6 +24,65 @@ pub struct Cluster {
     pub status: Option<ClusterStatus>,
 }
 
+impl Cluster {
+    /// Records the keys of the desired labels and annotations in the bookkeeping annotations,
+    /// so keys removed from the source can be removed from the Fleet cluster later.
+    pub(crate) fn track_propagated_keys(&mut self) {
+        let labels = join_keys(self.labels());
+        let annotations = join_keys(self.annotations());
+
+        let bookkeeping = self.annotations_mut();
+        bookkeeping.insert(PROPAGATED_LABELS_ANNOTATION.to_string(), labels);
+        bookkeeping.insert(PROPAGATED_ANNOTATIONS_ANNOTATION.to_string(), annotations);
+    }
+
+    /// Checks if the Fleet agent registered and reported back for the cluster.
+    pub(crate) fn agent_registered(&self) -> bool {
+        serde_json::to_value(&self.status)
+            .ok()
+            .and_then(|status| status.pointer("/agent/lastSeen").cloned())
+            .is_some_and(|last_seen| !last_seen.is_null())
+    }
+
+    /// Returns label and annotation keys previously propagated to the existing cluster,
+    /// which are no longer desired.
+    pub(crate) fn stale_keys(&self, existing: &Self) -> (Vec<String>, Vec<String>) {
+        let stale = |annotation: &str, desired: &BTreeMap<String, String>| -> Vec<String> {
+            existing
+                .annotations()
+                .get(annotation)
+                .map(|keys| split_keys(keys))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|key| !desired.contains_key(key))
+                .collect()
+        };
+
+        (
+            stale(PROPAGATED_LABELS_ANNOTATION, self.labels()),
+            stale(PROPAGATED_ANNOTATIONS_ANNOTATION, self.annotations()),
+        )
+    }
+}
+
+pub(crate) fn join_keys(map: &BTreeMap<String, String>) -> String {
+    map.keys()
+        .filter(|key| {
+            key.as_str() != PROPAGATED_LABELS_ANNOTATION
+                && key.as_str() != PROPAGATED_ANNOTATIONS_ANNOTATION
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(crate) fn split_keys(keys: &str) -> Vec<String> {
+    keys.split(',')
+        .filter(|key| !key.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 impl ResourceDiff for Cluster {
     fn diff(&self, other: &Self) -> bool {
         // Resource was just created
@@ -74,3 +139,65 @@ impl ResourceDiff for Cluster {
         !annotations_equal || !labels_equal || !owner_references_equal
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use kube::{ResourceExt as _, api::ObjectMeta};
+
+    use super::{Cluster, PROPAGATED_LABELS_ANNOTATION};
+
+    fn cluster(labels: &[&str], annotations: &[&str]) -> Cluster {
+        let map = |keys: &[&str]| -> BTreeMap<String, String> {
+            keys.iter()
+                .map(|key| ((*key).to_string(), "value".to_string()))
+                .collect()
+        };
+
+        let mut cluster = Cluster {
+            metadata: ObjectMeta {
+                labels: Some(map(labels)),
+                annotations: Some(map(annotations)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        cluster.track_propagated_keys();
+        cluster
+    }
+
+    #[test]
+    fn test_stale_keys() {
+        let existing = cluster(&["env", "team"], &["note", "owner"]);
+        assert_eq!(
+            existing.annotations().get(PROPAGATED_LABELS_ANNOTATION),
+            Some(&"env,team".to_string())
+        );
+
+        let desired = cluster(&["env"], &["owner"]);
+        assert_eq!(
+            desired.stale_keys(&existing),
+            (vec!["team".to_string()], vec!["note".to_string()])
+        );
+
+        let mut foreign = existing.clone();
+        foreign
+            .labels_mut()
+            .insert("set-by-fleet".into(), "true".into());
+        assert_eq!(existing.stale_keys(&foreign), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_agent_registered() {
+        assert!(!Cluster::default().agent_registered());
+
+        let registered: Cluster = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "cluster", "namespace": "default"},
+            "spec": {},
+            "status": {"agent": {"lastSeen": "2025-01-01T00:00:00Z"}},
+        }))
+        .unwrap();
+        assert!(registered.agent_registered());
+    }
+}
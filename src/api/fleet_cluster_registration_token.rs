@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use fleet_api_rs::fleet_cluster_registration_token::{
     ClusterRegistrationTokenSpec, ClusterRegistrationTokenStatus,
 };
@@ -24,3 +25,45 @@ impl ResourceDiff for ClusterRegistrationToken {
         self.spec != other.spec
     }
 }
+
+impl ClusterRegistrationToken {
+    /// Returns the expiration time of the token, once reported by Fleet.
+    pub(crate) fn expires(&self) -> Option<DateTime<Utc>> {
+        let status = serde_json::to_value(self.status.as_ref()?).ok()?;
+        DateTime::parse_from_rfc3339(status.pointer("/expires")?.as_str()?)
+            .ok()
+            .map(|expires| expires.with_timezone(&Utc))
+    }
+
+    /// Checks if the token expired at the given time.
+    pub(crate) fn expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires().is_some_and(|expires| expires <= now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+    use serde_json::json;
+
+    use super::ClusterRegistrationToken;
+
+    #[test]
+    fn test_token_expiry() {
+        let token: ClusterRegistrationToken = serde_json::from_value(json!({
+            "metadata": {"name": "cluster", "namespace": "default"},
+            "spec": {"ttl": "1h"},
+            "status": {"expires": "2025-01-01T01:00:00Z", "secretName": "cluster-token"},
+        }))
+        .unwrap();
+
+        let before = Utc.with_ymd_and_hms(2025, 1, 1, 0, 30, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 1, 30, 0).unwrap();
+        assert!(!token.expired(before));
+        assert!(token.expired(after));
+
+        let pending = ClusterRegistrationToken::default();
+        assert!(pending.expires().is_none());
+        assert!(!pending.expired(after));
+    }
+}
@@ -13,11 +13,20 @@ use kube::{
 use serde::{Deserialize, Serialize};
 
 use super::capi_clusterclass::ClusterClass;
+use super::fleet_addon_config::MetadataFilter;
+use super::fleet_cluster::{PROPAGATED_LABELS_ANNOTATION, join_keys, split_keys};
 use crate::api::comparable::ResourceDiff;
 
 pub static CLUSTER_CLASS_LABEL: &str = "clusterclass-name.fleet.addons.cluster.x-k8s.io";
 pub static CLUSTER_CLASS_NAMESPACE_LABEL: &str =
     "clusterclass-namespace.fleet.addons.cluster.x-k8s.io";
+/// Label set on `ClusterGroups` created from a group rule, with the rule name as value.
+pub static GROUP_RULE_LABEL: &str = "group-rule.fleet.addons.cluster.x-k8s.io";
+/// Prefix of the label keys marking Fleet clusters as members of a group rule.
+pub static GROUP_MEMBER_LABEL_PREFIX: &str = "group.fleet.addons.cluster.x-k8s.io/";
+/// Prefix of the `ClusterGroup` names created from a group rule. Class group names always
+/// contain a dot, so prefixed rule names never collide with them.
+pub static GROUP_RULE_NAME_PREFIX: &str = "rule-";
 
 #[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[resource(inherit = fleet_api_rs::fleet_clustergroup::ClusterGroup)]
@@ -86,20 +95,70 @@ impl ClusterGroup {
             Expression::Exists(CLUSTER_CLASS_NAMESPACE_LABEL.to_string()),
         ])
     }
+
+    pub(crate) fn rule_group_selector() -> Selector {
+        Selector::from_iter([Expression::Exists(GROUP_RULE_LABEL.to_string())])
+    }
+
+    /// Sets the `ClusterClass` labels selected by the filter on the group, next to the class
+    /// labels, and records the propagated keys in the bookkeeping annotation.
+    /// Keys recorded previously, which are no longer selected, are dropped from the group.
+    pub(crate) fn propagate_labels(
+        &mut self,
+        class: &ClusterClass,
+        filter: Option<&MetadataFilter>,
+    ) {
+        let mut desired = match filter {
+            Some(filter) => filter.filter(class.labels().clone()),
+            None => class.labels().clone(),
+        };
+        desired.insert(CLUSTER_CLASS_LABEL.to_string(), class.name_any());
+        desired.insert(
+            CLUSTER_CLASS_NAMESPACE_LABEL.to_string(),
+            class.namespace().unwrap_or_default(),
+        );
+
+        let recorded = self
+            .annotations()
+            .get(PROPAGATED_LABELS_ANNOTATION)
+            .map(|keys| split_keys(keys))
+            .unwrap_or_default();
+        let keys = join_keys(&desired);
+
+        let labels = self.labels_mut();
+        for key in recorded {
+            labels.remove(&key);
+        }
+        labels.extend(desired);
+        self.annotations_mut()
+            .insert(PROPAGATED_LABELS_ANNOTATION.to_string(), keys);
+    }
+
+    /// Returns label keys previously propagated to the existing group, which are no longer desired.
+    pub(crate) fn stale_labels(&self, existing: &Self) -> Vec<String> {
+        existing
+            .annotations()
+            .get(PROPAGATED_LABELS_ANNOTATION)
+            .map(|keys| split_keys(keys))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|key| !self.labels().contains_key(key))
+            .collect()
+    }
+
+    /// Returns the label key marking Fleet clusters as members of the group rule.
+    pub(crate) fn member_label(rule: &str) -> String {
+        format!("{GROUP_MEMBER_LABEL_PREFIX}{rule}")
+    }
+
+    /// Returns the name of the `ClusterGroup` created from the group rule.
+    pub(crate) fn rule_group_name(rule: &str) -> String {
+        format!("{GROUP_RULE_NAME_PREFIX}{rule}")
+    }
 }
 
 impl From<&ClusterClass> for ClusterGroup {
     fn from(cluster_class: &ClusterClass) -> Self {
-        let labels = {
-            let mut labels = cluster_class.labels().clone();
-            labels.insert(CLUSTER_CLASS_LABEL.to_string(), cluster_class.name_any());
-            labels.insert(
-                CLUSTER_CLASS_NAMESPACE_LABEL.to_string(),
-                cluster_class.namespace().unwrap_or_default(),
-            );
-            Some(labels)
-        };
-
         let match_labels = {
             let mut labels = BTreeMap::default();
             labels.insert(CLUSTER_CLASS_LABEL.to_string(), cluster_class.name_any());
@@ -115,7 +174,7 @@ impl From<&ClusterClass> for ClusterGroup {
             metadata: ObjectMeta {
                 name: Some(cluster_class.name_any()),
                 namespace: cluster_class.namespace(),
-                labels,
+                labels: match_labels.clone(),
                 owner_references: cluster_class
                     .owner_ref(&())
                     .into_iter()
@@ -133,3 +192,60 @@ impl From<&ClusterClass> for ClusterGroup {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kube::ResourceExt as _;
+    use serde_json::json;
+
+    use crate::api::capi_clusterclass::ClusterClass;
+    use crate::api::fleet_addon_config::{KeyPattern, MetadataFilter};
+    use crate::api::fleet_cluster::PROPAGATED_LABELS_ANNOTATION;
+
+    use super::{CLUSTER_CLASS_LABEL, ClusterGroup};
+
+    fn class(labels: serde_json::Value) -> ClusterClass {
+        serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "ClusterClass",
+            "metadata": {"name": "quick-start", "namespace": "classes", "labels": labels},
+            "spec": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_propagate_labels() {
+        let filter = MetadataFilter {
+            exclude: vec![KeyPattern::Prefix("internal/".into())],
+            ..Default::default()
+        };
+
+        let source = class(json!({"env": "prod", "tier": "gold", "internal/id": "1"}));
+        let mut existing: ClusterGroup = (&source).into();
+        existing.propagate_labels(&source, Some(&filter));
+        existing
+            .labels_mut()
+            .insert("external".into(), "kept".into());
+        assert_eq!(existing.labels()["env"], "prod");
+        assert_eq!(existing.labels()[CLUSTER_CLASS_LABEL], "quick-start");
+        assert!(!existing.labels().contains_key("internal/id"));
+
+        // Dropped class labels are removed, labels set by others are kept
+        let source = class(json!({"env": "prod"}));
+        let mut desired = existing.clone();
+        desired.propagate_labels(&source, Some(&filter));
+        assert!(!desired.labels().contains_key("tier"));
+        assert_eq!(desired.labels()["external"], "kept");
+        assert!(
+            !desired.annotations()[PROPAGATED_LABELS_ANNOTATION]
+                .split(',')
+                .any(|key| key == "tier")
+        );
+        assert_eq!(desired.stale_labels(&existing), vec!["tier".to_string()]);
+
+        let mut fresh: ClusterGroup = (&source).into();
+        fresh.propagate_labels(&source, Some(&filter));
+        assert_eq!(fresh.stale_labels(&existing), vec!["tier".to_string()]);
+    }
+}
@@ -3,6 +3,8 @@ pub mod capi_cluster;
 pub mod capi_clusterclass;
 pub mod comparable;
 pub mod fleet_addon_config;
+pub mod fleet_addon_config_override;
+pub mod fleet_bundle_deployment;
 pub mod fleet_cluster;
 #[cfg(feature = "agent-initiated")]
 pub mod fleet_cluster_registration_token;
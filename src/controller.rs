@@ -2,9 +2,16 @@ use crate::api::bundle_namespace_mapping::BundleNamespaceMapping;
 use crate::api::capi_cluster::Cluster;
 use crate::api::capi_clusterclass::ClusterClass;
 use crate::api::fleet_addon_config::FleetAddonConfig;
+use crate::api::fleet_addon_config_override::FleetAddonConfigOverride;
+use crate::api::fleet_bundle_deployment::BundleDeployment;
 use crate::api::fleet_cluster;
-use crate::api::fleet_clustergroup::ClusterGroup;
+use crate::api::fleet_clustergroup::{
+    CLUSTER_CLASS_LABEL, CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup,
+};
 use crate::controllers::addon_config::FleetConfig;
+use crate::controllers::cluster::{
+    BUNDLE_DEPLOYMENT_CLUSTER_LABEL, BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL, FleetClusterIndex,
+};
 use crate::controllers::controller::{Context, DynamicStream, FleetController, fetch_config};
 use crate::metrics::Diagnostics;
 use crate::multi_dispatcher::{BroadcastStream, MultiDispatcher, broadcaster};
@@ -12,10 +19,11 @@ use crate::{Error, Metrics};
 
 use chrono::Local;
 use clap::Parser;
-use futures::{Stream, StreamExt};
+use futures::lock::Mutex;
+use futures::{Stream, StreamExt, stream};
 
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{Condition, Time};
-use kube::api::{Patch, PatchParams};
+use kube::api::{GroupVersionKind, Patch, PatchParams};
 use kube::core::DeserializeGuard;
 use kube::runtime::reflector::ObjectRef;
 use kube::runtime::reflector::store::Writer;
@@ -31,9 +39,10 @@ use kube::{
 };
 use tokio::sync::Barrier;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::{sync::RwLock, time::Duration};
 use tracing::{self, warn};
@@ -54,6 +63,12 @@ pub struct State {
     dispatcher: MultiDispatcher,
     // shared stream of dynamic events
     stream: BroadcastStream<DynamicStream>,
+    // shared stream of objects referenced by clusters
+    references: BroadcastStream<DynamicStream>,
+    // kinds of referenced objects with an active watch
+    reference_kinds: Arc<RwLock<HashSet<GroupVersionKind>>>,
+    // imported Fleet clusters by the CAPI cluster they are created from
+    fleet_clusters: FleetClusterIndex,
 
     // k8s api server minor version
     pub version: u32,
@@ -67,6 +82,15 @@ pub struct Flags {
     /// helm install allows to select container for performing fleet chart installation
     #[arg(long)]
     pub helm_install: bool,
+
+    /// Directory with `tls.crt` and `tls.key` files used to serve webhooks.
+    /// Webhooks are not served if not set.
+    #[arg(long)]
+    pub webhook_cert_dir: Option<PathBuf>,
+
+    /// Port for serving webhooks
+    #[arg(long, default_value_t = 9443)]
+    pub webhook_port: u16,
 }
 
 impl State {
@@ -83,6 +107,12 @@ impl State {
             dispatcher: MultiDispatcher::new(128),
             diagnostics: Arc::default(),
             stream: BroadcastStream::new(Arc::default()),
+            // Pending stream keeps the shared stream open until the first reference watch is added
+            references: BroadcastStream::new(Arc::new(Mutex::new(stream::select_all([
+                stream::pending().boxed(),
+            ])))),
+            reference_kinds: Arc::default(),
+            fleet_clusters: FleetClusterIndex::default(),
             version,
             barrier: Arc::new(Barrier::new(3)),
         }
@@ -108,6 +138,9 @@ impl State {
             diagnostics: self.diagnostics.clone(),
             dispatcher: self.dispatcher.clone(),
             stream: self.stream.clone(),
+            references: self.references.clone(),
+            reference_kinds: self.reference_kinds.clone(),
+            fleet_clusters: self.fleet_clusters.clone(),
             version: self.version,
             barrier: self.barrier.clone(),
         })
@@ -183,6 +216,24 @@ pub async fn run_fleet_addon_config_controller(state: State) {
     .default_backoff()
     .for_each(|_| futures::future::ready(()));
 
+    // Status updates are ignored, garbage collection is repeated on the configured interval
+    let (reader, writer) = reflector::store();
+    let garbage_collection_config = watcher(
+        Api::<FleetAddonConfig>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_with_reflect(writer)
+    .predicate_filter(predicates::generation);
+    let garbage_collection_controller = Controller::for_stream(garbage_collection_config, reader)
+        .shutdown_on_signal()
+        .run(
+            FleetAddonConfig::reconcile_garbage_collection,
+            error_policy,
+            state.to_context(client.clone()),
+        )
+        .default_backoff()
+        .for_each(|_| futures::future::ready(()));
+
     let watcher = broadcaster(state.dispatcher.clone(), state.stream.clone())
         .for_each(|_| futures::future::ready(()));
 
@@ -201,7 +252,7 @@ pub async fn run_fleet_addon_config_controller(state: State) {
 
     tokio::select! {
         () = watcher => {panic!("This should not happen before controllers exit")},
-        _ = futures::future::join(dynamic_watches_controller, config_controller) => {}
+        _ = futures::future::join3(dynamic_watches_controller, config_controller, garbage_collection_controller) => {}
     };
 }
 
@@ -322,16 +373,69 @@ pub async fn run_cluster_controller(state: State) {
     )
     .default_handling();
 
+    let rule_groups = metadata_watcher(
+        Api::<ClusterGroup>::all(client.clone()),
+        Config::default()
+            .labels_from(&ClusterGroup::rule_group_selector())
+            .any_semantic(),
+    )
+    .default_handling();
+
     let mappings = metadata_watcher(
         Api::<BundleNamespaceMapping>::all(client.clone()),
         Config::default().any_semantic(),
     )
     .default_handling();
 
+    let bundle_deployments = metadata_watcher(
+        Api::<BundleDeployment>::all(client.clone()),
+        Config::default()
+            .labels(BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL)
+            .any_semantic(),
+    )
+    .default_handling();
+
+    let config_overrides = metadata_watcher(
+        Api::<FleetAddonConfigOverride>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_handling();
+
     let (sub, reader) = state.dispatcher.subscribe();
+    let bundle_reader = reader.clone();
+    let override_reader = reader.clone();
+    let reference_reader = reader.clone();
+    let fleet_clusters = state.fleet_clusters.clone();
+
+    // Control plane and infrastructure kinds are discovered from the cluster references
+    // and added to the shared stream during cluster reconciliation. Only spec changes
+    // are relevant for templating, so status-only updates are filtered out.
+    let references = state
+        .references
+        .clone()
+        .touched_objects()
+        .predicate_filter(predicates::generation)
+        .default_backoff()
+        .filter_map(|object| {
+            futures::future::ready(
+                object
+                    .inspect_err(|e| warn!("Referenced objects watch failed: {e}"))
+                    .ok(),
+            )
+        })
+        .flat_map(move |object| {
+            let clusters: Vec<_> = reference_reader
+                .state()
+                .into_iter()
+                .filter(|c: &Arc<Cluster>| c.references(&object))
+                .map(|c| ObjectRef::from_obj(&*c))
+                .collect();
+            stream::iter(clusters)
+        });
     let clusters = Controller::for_shared_stream(sub, reader.clone())
         .owns_stream(fleet)
         .owns_stream(groups)
+        .owns_stream(rule_groups)
         .watches_stream(mappings, move |mapping| {
             reader
                 .state()
@@ -342,6 +446,40 @@ pub async fn run_cluster_controller(state: State) {
                     in_namespace.then_some(ObjectRef::from_obj(&*c))
                 })
         })
+        .watches_stream(bundle_deployments, move |bundle| {
+            let cluster_namespace = bundle
+                .labels()
+                .get(BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL)
+                .cloned();
+            let cluster_name = bundle
+                .labels()
+                .get(BUNDLE_DEPLOYMENT_CLUSTER_LABEL)
+                .cloned()
+                .unwrap_or_default();
+            // Fleet cluster names follow the configured naming, clusters which were not
+            // reconciled since the controller start are matched by their own name.
+            let indexed = fleet_clusters.get(&cluster_name, cluster_namespace.as_deref());
+            let unindexed = indexed.is_none().then(|| {
+                bundle_reader
+                    .state()
+                    .into_iter()
+                    .filter_map(move |c: Arc<Cluster>| {
+                        (c.namespace() == cluster_namespace && c.name_any() == cluster_name)
+                            .then_some(ObjectRef::from_obj(&*c))
+                    })
+            });
+            indexed.into_iter().chain(unindexed.into_iter().flatten())
+        })
+        .watches_stream(config_overrides, move |config_override| {
+            override_reader
+                .state()
+                .into_iter()
+                .filter_map(move |c: Arc<Cluster>| {
+                    (c.namespace() == config_override.namespace())
+                        .then_some(ObjectRef::from_obj(&*c))
+                })
+        })
+        .reconcile_on(references)
         .shutdown_on_signal()
         .run(
             Cluster::reconcile,
@@ -397,8 +535,13 @@ pub async fn run_cluster_class_controller(state: State) {
     )
     .default_handling();
 
+    // Groups are mapped by labels, as owner references are optional
     let cluster_class_controller = Controller::for_stream(cluster_classes, reader)
-        .owns_stream(groups)
+        .watches_stream(groups, |group| {
+            let name = group.labels().get(CLUSTER_CLASS_LABEL)?;
+            let namespace = group.labels().get(CLUSTER_CLASS_NAMESPACE_LABEL)?;
+            Some(ObjectRef::<ClusterClass>::new(name).within(namespace))
+        })
         .shutdown_on_signal()
         .run(
             ClusterClass::reconcile,
@@ -5,7 +5,8 @@ use futures::StreamExt as _;
 use std::{fmt::Display, io, str::FromStr, sync::Arc, time::Duration};
 
 use k8s_openapi::{
-    api::core::v1::{self, ConfigMap, Endpoints, ObjectReference},
+    ByteString,
+    api::core::v1::{self, ConfigMap, Endpoints, ObjectReference, Secret},
     apimachinery::pkg::apis::meta::v1::{Condition, Time},
 };
 use kube::{
@@ -29,7 +30,8 @@ use crate::{
         capi_cluster::Cluster,
         comparable::ResourceDiff,
         fleet_addon_config::{
-            FeatureGates, FleetAddonConfig, FleetSettings, Install, InstallOptions, Server,
+            FeatureGates, FleetAddonConfig, FleetAddonConfigStatus, FleetInstall, FleetSettings,
+            InstallOptions, Server, ValuesSourceKind,
         },
     },
     telemetry,
@@ -38,9 +40,15 @@ use crate::{
 use super::{
     PatchError,
     controller::{Context, patch},
-    helm::{self, install::FleetChart},
+    helm::{
+        self, RepoAddResult,
+        install::{ChartAuth, ChartInfo, FleetChart, Resolution, merge_values, values_drift},
+    },
 };
 
+/// Interval for re-reading the `valuesFrom` sources, which are not watched.
+const VALUES_SOURCES_RECHECK: Duration = Duration::from_secs(300);
+
 #[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
 #[resource(inherit = ConfigMap)]
 pub struct FleetConfig {
@@ -104,6 +112,19 @@ struct CertData {
     ca_crt: String,
 }
 
+#[derive(Resource, Deserialize, Serialize, Clone, Debug)]
+#[resource(inherit = Secret)]
+struct CredentialsSecret {
+    metadata: ObjectMeta,
+    data: CredentialsData,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct CredentialsData {
+    username: ByteString,
+    password: ByteString,
+}
+
 impl FleetAddonConfig {
     #[instrument(skip_all, fields(reconcile_id, name = self.name_any(), namespace = self.namespace()))]
     pub async fn reconcile_helm(&mut self, ctx: Arc<Context>) -> crate::Result<Action> {
@@ -112,15 +133,31 @@ impl FleetAddonConfig {
             return Ok(requeue);
         }
 
+        let install = self.spec.install.as_ref();
+        let (auth, values) = match install {
+            Some(install) => (
+                install.chart_auth(ctx.clone()).await?,
+                install.chart_values(ctx.clone()).await?,
+            ),
+            None => (ChartAuth::default(), Value::Null),
+        };
         let chart = FleetChart {
-            repo: "https://rancher.github.io/fleet-helm-charts/".into(),
-            namespace: "cattle-fleet-system".into(),
+            source: install.into(),
+            auth,
+            namespace: self.spec.fleet_namespace().into(),
             wait: true,
             update_dependency: true,
             create_namespace: true,
             bootstrap_local_cluster: self.spec.bootstrap().unwrap_or_default(),
             feature_gates: self.spec.feature_gates().cloned().unwrap_or_default(),
             version: Option::default(),
+            values,
+            allow_prerelease: install
+                .and_then(|install| install.allow_prerelease)
+                .unwrap_or_default(),
+            allow_downgrade: install
+                .and_then(|install| install.allow_downgrade)
+                .unwrap_or_default(),
         };
 
         let status = self.status.get_or_insert_default();
@@ -128,7 +165,7 @@ impl FleetAddonConfig {
 
         status.conditions.push(Condition {
             last_transition_time: Time(Local::now().to_utc()),
-            message: format!("Repo added: {}", chart.repo),
+            message: format!("Repo added: {}", chart.source),
             observed_generation: self.metadata.generation,
             reason: "RepoAdd".into(),
             status: "True".into(),
@@ -137,16 +174,28 @@ impl FleetAddonConfig {
 
         if let Some(install) = &self.spec.install {
             if let Some(requeue) = self
-                .install_fleet(FleetChart {
-                    version: Some(install.install_version.clone()),
-                    ..chart.clone()
-                })
+                .install_fleet(
+                    ctx.clone(),
+                    FleetChart {
+                        version: Some(install.install_version.clone()),
+                        ..chart.clone()
+                    },
+                )
                 .await?
             {
                 return Ok(requeue);
             }
         }
 
+        let values_from = self
+            .spec
+            .install
+            .as_ref()
+            .and_then(|install| install.values_from.as_ref());
+        if values_from.is_some_and(|sources| !sources.is_empty()) {
+            return Ok(Action::requeue(VALUES_SOURCES_RECHECK));
+        }
+
         return Ok(Action::await_change());
     }
 
@@ -156,7 +205,7 @@ impl FleetAddonConfig {
         ctx: Arc<Context>,
     ) -> ReconcileConfigSyncResult<Action> {
         let _current = Span::current().record("reconcile_id", display(telemetry::get_trace_id()));
-        let ns = Namespace::from("cattle-fleet-system");
+        let ns = Namespace::from(self.spec.fleet_namespace());
         let mut fleet_config: FleetConfig = ctx.client.get("fleet-controller", &ns).await?;
 
         if let Some(server) = self.spec().config.as_ref().and_then(|c| c.server.as_ref()) {
@@ -300,101 +349,113 @@ impl FleetAddonConfig {
         Ok(())
     }
 
-    async fn install_fleet(&mut self, chart: FleetChart) -> AddonConfigSyncResult<Option<Action>> {
-        let expected_version = match chart.version.as_ref() {
-            Some(version) => &version.clone().normalized(),
-            None => return Ok(None),
-        };
+    async fn install_fleet(
+        &mut self,
+        ctx: Arc<Context>,
+        chart: FleetChart,
+    ) -> AddonConfigSyncResult<Option<Action>> {
+        if chart.version.is_none() {
+            return Ok(None);
+        }
 
         let status = self.status.get_or_insert_default();
-        let installed_chart_meta = FleetChart::get_metadata("fleet-crd").await?;
-        let search_result = chart
-            .search_repo()
-            .await?
-            .into_iter()
-            .find(|r| r.name == "fleet/fleet-crd");
-        match (installed_chart_meta, search_result, expected_version) {
-            (Some(installed), Some(search), Install::FollowLatest(true))
-                if search.chart.metadata.app_version != installed.chart.metadata.app_version =>
-            {
-                chart.upgrade_fleet_crds().await?;
-            }
-            (Some(installed), Some(_), Install::Version(expected))
-                if expected.strip_prefix("v").unwrap_or(expected)
-                    != installed.chart.metadata.app_version =>
-            {
-                chart.upgrade_fleet_crds().await?;
+        let available = chart.search_repo()?;
+        let installed_crds = chart.get_release(ctx.client.clone(), "fleet-crd").await?;
+        match chart.resolve("fleet-crd", installed_crds.as_ref(), &available) {
+            Resolution::Install(version) => {
+                chart.install_fleet_crds(&version).await?;
             }
-            (None, Some(_), _) => {
-                chart.install_fleet_crds().await?;
+            Resolution::Upgrade(version) => {
+                chart.upgrade_fleet_crds(&version).await?;
             }
-            (Some(_), Some(_), Install::FollowLatest(_) | Install::Version(_)) => {}
-            (_, _, _) => return Ok(Some(Action::requeue(Duration::from_secs(10)))),
+            Resolution::Keep | Resolution::DowngradeBlocked { .. } => {}
+            Resolution::Unavailable => return Ok(Some(Action::requeue(Duration::from_secs(10)))),
         }
 
-        let installed_chart_meta = FleetChart::get_metadata("fleet").await?;
-        let search_result = chart
-            .search_repo()
-            .await?
-            .into_iter()
-            .find(|r| r.name == "fleet/fleet");
+        let values_hash = chart.values_hash();
+        let installed_chart_meta = chart.get_release(ctx.client.clone(), "fleet").await?;
+        let resolution = chart.resolve("fleet", installed_chart_meta.as_ref(), &available);
+
+        let installed = installed_chart_meta.is_some();
+        let drift = installed_chart_meta
+            .as_ref()
+            .map(|release| values_drift(&release.config, &chart.values()))
+            .unwrap_or_default();
+        // Values changed since the last install or upgrade, including the `valuesFrom` sources
+        let values_changed = status
+            .values_hash
+            .as_ref()
+            .is_some_and(|hash| *hash != values_hash);
+
+        if let Resolution::DowngradeBlocked {
+            installed: current,
+            selected,
+        } = &resolution
+        {
+            status.conditions.push(Condition {
+                last_transition_time: Time(Local::now().to_utc()),
+                message: format!(
+                    "Skipped fleet downgrade from {current} to {selected}, allowDowngrade is not set"
+                ),
+                observed_generation: self.metadata.generation,
+                reason: "DowngradeBlocked".into(),
+                status: "True".into(),
+                type_: "Installed".into(),
+            });
+        }
 
-        match (
+        let generation = self.metadata.generation;
+        let result = apply_release(
+            &chart,
             installed_chart_meta,
-            search_result.as_ref(),
-            expected_version,
-        ) {
-            (Some(installed), Some(search), Install::FollowLatest(true))
-                if search.chart.metadata.app_version != installed.chart.metadata.app_version =>
-            {
-                let info = chart.upgrade_fleet().await?;
-                let version = info.chart.metadata.app_version;
-                status.installed_version = Some(version.clone());
-                status.conditions.push(Condition {
-                    last_transition_time: Time(Local::now().to_utc()),
-                    message: format!("Updated fleet to version {version}"),
-                    observed_generation: self.metadata.generation,
-                    reason: "Installed".into(),
-                    status: "True".into(),
-                    type_: "Installed".into(),
-                });
-            }
-            (Some(installed), Some(_), Install::Version(expected))
-                if expected.strip_prefix("v").unwrap_or(expected)
-                    != installed.chart.metadata.app_version =>
-            {
-                let info = chart.upgrade_fleet().await?;
-                let version = info.chart.metadata.app_version;
-                status.installed_version = Some(version.clone());
-                status.conditions.push(Condition {
-                    last_transition_time: Time(Local::now().to_utc()),
-                    message: format!("Updated fleet to version {version}"),
-                    observed_generation: self.metadata.generation,
-                    reason: "Installed".into(),
-                    status: "True".into(),
-                    type_: "Installed".into(),
-                });
-            }
-            (None, Some(_), Install::FollowLatest(_) | Install::Version(_)) => {
-                let info = chart.install_fleet().await?;
-                let version = info.chart.metadata.app_version;
-                status.installed_version = Some(version.clone());
-                status.conditions.push(Condition {
-                    last_transition_time: Time(Local::now().to_utc()),
-                    message: format!("Installed fleet version {version}"),
-                    observed_generation: self.metadata.generation,
-                    reason: "Installed".into(),
-                    status: "True".into(),
-                    type_: "Installed".into(),
-                });
-            }
-            (Some(installed), Some(_), Install::FollowLatest(_) | Install::Version(_)) => {
-                status.installed_version = installed.chart.metadata.app_version.into();
-            }
-            (_, _, _) => return Ok(Some(Action::requeue(Duration::from_secs(10)))),
+            resolution,
+            values_changed || !drift.is_empty(),
+            status,
+            generation,
+        )
+        .await;
+
+        // A single drift condition per reconcile, reflecting the outcome of the upgrade
+        if installed {
+            let keys = drift.join(", ");
+            let (drifted, reason, message) = match &result {
+                _ if drift.is_empty() => (
+                    "False",
+                    "InSync",
+                    "Fleet release values match the desired values".to_string(),
+                ),
+                Ok(None) => (
+                    "False",
+                    "DriftResolved",
+                    format!("Upgraded fleet to resolve values drift in: {keys}"),
+                ),
+                Ok(Some(_)) => (
+                    "True",
+                    "UpgradeSkipped",
+                    format!("Fleet release values differ in: {keys}, the upgrade is postponed"),
+                ),
+                Err(e) => (
+                    "True",
+                    "UpgradeFailed",
+                    format!("Fleet release values differ in: {keys}, the upgrade failed: {e}"),
+                ),
+            };
+            status.conditions.push(Condition {
+                last_transition_time: Time(Local::now().to_utc()),
+                message,
+                observed_generation: generation,
+                reason: reason.into(),
+                status: drifted.into(),
+                type_: "ValuesDrift".into(),
+            });
         }
 
-        Ok(None)
+        let requeue = result?;
+        if requeue.is_none() {
+            status.values_hash = Some(values_hash);
+        }
+
+        Ok(requeue)
     }
 
     async fn update_flags(&mut self, ctx: Arc<Context>) -> FleetPatchResult<Option<Action>> {
@@ -426,6 +487,125 @@ impl FleetAddonConfig {
     }
 }
 
+/// Installs or upgrades the Fleet release according to the resolution, and records the
+/// installed version. Releases with changed or drifted values are upgraded in place.
+/// Returns a requeue action, if the release can't be brought to the desired state yet.
+async fn apply_release(
+    chart: &FleetChart,
+    installed_chart_meta: Option<ChartInfo>,
+    resolution: Resolution,
+    values_changed: bool,
+    status: &mut FleetAddonConfigStatus,
+    generation: Option<i64>,
+) -> AddonConfigSyncResult<Option<Action>> {
+    match (installed_chart_meta, resolution) {
+        (_, Resolution::Install(version)) => {
+            let info = chart.install_fleet(&version).await?;
+            let version = info.chart.metadata.app_version;
+            status.installed_version = Some(version.clone());
+            status.conditions.push(Condition {
+                last_transition_time: Time(Local::now().to_utc()),
+                message: format!("Installed fleet version {version}"),
+                observed_generation: generation,
+                reason: "Installed".into(),
+                status: "True".into(),
+                type_: "Installed".into(),
+            });
+        }
+        (_, Resolution::Upgrade(version)) => {
+            let info = chart.upgrade_fleet(&version).await?;
+            let version = info.chart.metadata.app_version;
+            status.installed_version = Some(version.clone());
+            status.conditions.push(Condition {
+                last_transition_time: Time(Local::now().to_utc()),
+                message: format!("Updated fleet to version {version}"),
+                observed_generation: generation,
+                reason: "Installed".into(),
+                status: "True".into(),
+                type_: "Installed".into(),
+            });
+        }
+        (Some(installed), Resolution::Keep | Resolution::DowngradeBlocked { .. })
+            if values_changed =>
+        {
+            let info = chart
+                .upgrade_fleet(&installed.chart.metadata.chart_version())
+                .await?;
+            let version = info.chart.metadata.app_version;
+            status.installed_version = Some(version.clone());
+            status.conditions.push(Condition {
+                last_transition_time: Time(Local::now().to_utc()),
+                message: format!("Updated fleet version {version} chart values"),
+                observed_generation: generation,
+                reason: "Installed".into(),
+                status: "True".into(),
+                type_: "Installed".into(),
+            });
+        }
+        (Some(installed), Resolution::Keep | Resolution::DowngradeBlocked { .. }) => {
+            status.installed_version = installed.chart.metadata.app_version.into();
+        }
+        (_, _) => return Ok(Some(Action::requeue(Duration::from_secs(10)))),
+    }
+
+    Ok(None)
+}
+
+impl FleetInstall {
+    /// Reads the chart source credentials and stores the CA bundle in a file for helm to use.
+    async fn chart_auth(&self, ctx: Arc<Context>) -> RepoAddResult<ChartAuth> {
+        let mut auth = ChartAuth::default();
+        if let Some(reference) = &self.credentials_secret_ref {
+            let secret: CredentialsSecret = ctx.client.fetch(reference).await?;
+            auth.username = String::from_utf8_lossy(&secret.data.username.0).into();
+            auth.password = String::from_utf8_lossy(&secret.data.password.0).into();
+        }
+
+        if let Some(reference) = &self.ca_bundle_ref {
+            let ca: CertConfigMap = ctx.client.fetch(reference).await?;
+            let path = std::env::temp_dir().join("fleet-chart-ca.crt");
+            std::fs::write(&path, ca.data.ca_crt)?;
+            auth.ca_file = path.to_string_lossy().into();
+        }
+
+        Ok(auth)
+    }
+
+    /// Merges the values from the `valuesFrom` sources in order, followed by the inline values.
+    async fn chart_values(&self, ctx: Arc<Context>) -> ChartValuesResult<Value> {
+        let mut values = Value::Null;
+        for reference in self.values_from.iter().flatten() {
+            let key = reference.values_key();
+            let data = match reference.kind {
+                ValuesSourceKind::ConfigMap => {
+                    Api::<ConfigMap>::namespaced(ctx.client.clone(), &reference.namespace)
+                        .get(&reference.name)
+                        .await?
+                        .data
+                        .and_then(|mut data| data.remove(key))
+                        .map(String::into_bytes)
+                }
+                ValuesSourceKind::Secret => {
+                    Api::<Secret>::namespaced(ctx.client.clone(), &reference.namespace)
+                        .get(&reference.name)
+                        .await?
+                        .data
+                        .and_then(|mut data| data.remove(key))
+                        .map(|data| data.0)
+                }
+            };
+            let data = data.ok_or_else(|| ChartValuesError::MissingKey(reference.to_string()))?;
+            merge_values(&mut values, serde_yaml::from_slice(&data)?);
+        }
+
+        if let Some(inline) = &self.values {
+            merge_values(&mut values, inline.clone());
+        }
+
+        Ok(values)
+    }
+}
+
 impl FeatureGates {
     async fn update_config_map(
         &self,
@@ -536,6 +716,20 @@ pub enum AddonConfigSyncError {
     CommandError(#[from] io::Error),
 }
 
+pub type ChartValuesResult<T> = std::result::Result<T, ChartValuesError>;
+
+#[derive(Error, Debug)]
+pub enum ChartValuesError {
+    #[error("Chart values source fetch error: {0}")]
+    Fetch(#[from] kube::Error),
+
+    #[error("Chart values not found in {0}")]
+    MissingKey(String),
+
+    #[error("Chart values parse error: {0}")]
+    Parse(#[from] serde_yaml::Error),
+}
+
 pub type DynamicWatcherResult<T> = std::result::Result<T, DynamicWatcherError>;
 
 #[derive(Error, Debug)]
@@ -0,0 +1,522 @@
+use base64::prelude::*;
+use k8s_openapi::api::core::v1::Secret;
+use kube::api::{ApiResource, DeleteParams, DynamicObject, GroupVersionKind, Patch, PatchParams};
+use kube::client::scope::Namespace;
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Api, Client, Resource, ResourceExt as _};
+use serde_json::{Value, json};
+use tracing::{debug, info};
+
+use crate::api::capi_cluster::Cluster;
+use crate::api::fleet_addon_config::{AgentBootstrap, AgentBootstrapDelivery, FleetAddonConfig};
+use crate::api::fleet_cluster;
+use crate::api::fleet_cluster_registration_token::ClusterRegistrationToken;
+
+use super::addon_config::FleetConfig;
+use super::controller::GetApi as _;
+use super::{AgentBootstrapError, AgentBootstrapResult};
+
+/// Label selecting the CAPI cluster in the agent bootstrap `ClusterResourceSet`.
+pub static AGENT_BOOTSTRAP_LABEL: &str = "agent-bootstrap.fleet.addons.cluster.x-k8s.io";
+
+/// Secret read by the Fleet agent on the first start to register the cluster.
+static BOOTSTRAP_SECRET: &str = "fleet-agent-bootstrap";
+/// Key of the agent manifests in the `ClusterResourceSet` secret.
+static MANIFESTS_KEY: &str = "fleet-agent.yaml";
+
+/// Values of the agent initiated registration, rendered into the Fleet agent manifests.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct AgentRegistration {
+    namespace: String,
+    client_id: String,
+    image: String,
+    token: String,
+    api_server_url: String,
+    /// Base64 encoded CA of the Fleet API server, as stored in the Fleet controller config.
+    api_server_ca: String,
+    system_registration_namespace: String,
+    cluster_namespace: String,
+    host_network: bool,
+    tolerations: Value,
+}
+
+impl AgentRegistration {
+    /// Collects the registration values from the token secret, the Fleet `Cluster` and the
+    /// Fleet controller config. Returns `None` until Fleet populated the token secret.
+    pub(crate) async fn fetch(
+        client: Client,
+        token: &ClusterRegistrationToken,
+        fleet: &fleet_cluster::Cluster,
+        config: &FleetAddonConfig,
+    ) -> AgentBootstrapResult<Option<Self>> {
+        let Some(cluster_config) = config.spec.cluster.as_ref() else {
+            return Ok(None);
+        };
+        let Some(image) = agent_image(config, cluster_config.agent_bootstrap.as_ref()) else {
+            debug!("Fleet is not installed yet, postponing agent bootstrap");
+            return Ok(None);
+        };
+        let Some(client_id) = fleet.spec.client_id.clone() else {
+            return Ok(None);
+        };
+
+        let secret_name = serde_json::to_value(&token.status)?
+            .pointer("/secretName")
+            .and_then(Value::as_str)
+            .map(String::from);
+        let Some(secret_name) = secret_name else {
+            return Ok(None);
+        };
+        let Some(secret) = Api::<Secret>::namespaced(client.clone(), token.get_namespace())
+            .get_opt(&secret_name)
+            .await
+            .map_err(AgentBootstrapError::Lookup)?
+        else {
+            return Ok(None);
+        };
+        let Some(values) = secret.data.as_ref().and_then(|data| data.get("values")) else {
+            return Ok(None);
+        };
+        let values: Value = serde_yaml::from_slice(&values.0)?;
+
+        let fleet_config: FleetConfig = client
+            .get(
+                "fleet-controller",
+                &Namespace::from(config.spec.fleet_namespace()),
+            )
+            .await
+            .map_err(AgentBootstrapError::Lookup)?;
+
+        let value = |key: &str| values[key].as_str().unwrap_or_default().to_string();
+        Ok(Some(Self {
+            namespace: cluster_config.agent_install_namespace(),
+            client_id,
+            image,
+            token: value("token"),
+            api_server_url: fleet_config.data.config.api_server_url,
+            api_server_ca: fleet_config.data.config.api_server_ca,
+            system_registration_namespace: value("systemRegistrationNamespace"),
+            cluster_namespace: value("clusterNamespace"),
+            host_network: cluster_config.host_network.unwrap_or_default(),
+            tolerations: serde_json::to_value(cluster_config.agent_tolerations())?,
+        }))
+    }
+
+    /// Renders the Fleet agent manifests. The agent registers with the bootstrap secret,
+    /// after which Fleet takes over the agent deployment.
+    pub(crate) fn manifests(&self) -> Vec<Value> {
+        let Self {
+            namespace,
+            client_id,
+            image,
+            token,
+            api_server_url,
+            api_server_ca,
+            system_registration_namespace,
+            cluster_namespace,
+            host_network,
+            tolerations,
+        } = self;
+        let encode = |value: &str| BASE64_STANDARD.encode(value);
+
+        vec![
+            json!({
+                "apiVersion": "v1",
+                "kind": "Namespace",
+                "metadata": {"name": namespace},
+            }),
+            json!({
+                "apiVersion": "v1",
+                "kind": "Secret",
+                "metadata": {"name": BOOTSTRAP_SECRET, "namespace": namespace},
+                "data": {
+                    "token": encode(token),
+                    "apiServerURL": encode(api_server_url),
+                    "apiServerCA": api_server_ca,
+                    "systemRegistrationNamespace": encode(system_registration_namespace),
+                    "clusterNamespace": encode(cluster_namespace),
+                },
+            }),
+            json!({
+                "apiVersion": "v1",
+                "kind": "ConfigMap",
+                "metadata": {"name": "fleet-agent", "namespace": namespace},
+                "data": {
+                    "config": json!({"clientID": client_id}).to_string(),
+                },
+            }),
+            json!({
+                "apiVersion": "v1",
+                "kind": "ServiceAccount",
+                "metadata": {"name": "fleet-agent", "namespace": namespace},
+            }),
+            json!({
+                "apiVersion": "rbac.authorization.k8s.io/v1",
+                "kind": "ClusterRoleBinding",
+                "metadata": {"name": format!("fleet-agent-{namespace}")},
+                "roleRef": {
+                    "apiGroup": "rbac.authorization.k8s.io",
+                    "kind": "ClusterRole",
+                    "name": "cluster-admin",
+                },
+                "subjects": [{
+                    "kind": "ServiceAccount",
+                    "name": "fleet-agent",
+                    "namespace": namespace,
+                }],
+            }),
+            json!({
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "metadata": {"name": "fleet-agent", "namespace": namespace},
+                "spec": {
+                    "selector": {"matchLabels": {"app": "fleet-agent"}},
+                    "template": {
+                        "metadata": {"labels": {"app": "fleet-agent"}},
+                        "spec": {
+                            "serviceAccountName": "fleet-agent",
+                            "hostNetwork": host_network,
+                            "tolerations": tolerations,
+                            "containers": [{
+                                "name": "fleet-agent",
+                                "image": image,
+                                "command": ["fleetagent"],
+                                "env": [{
+                                    "name": "NAMESPACE",
+                                    "valueFrom": {"fieldRef": {"fieldPath": "metadata.namespace"}},
+                                }],
+                            }],
+                        },
+                    },
+                },
+            }),
+        ]
+    }
+
+    /// Delivers the agent manifests into the workload cluster with the configured method.
+    pub(crate) async fn deliver(
+        &self,
+        client: Client,
+        cluster: &Cluster,
+        bootstrap: &AgentBootstrap,
+    ) -> AgentBootstrapResult<()> {
+        match bootstrap.delivery {
+            AgentBootstrapDelivery::ClusterResourceSet => {
+                self.apply_resource_set(client, cluster).await
+            }
+            AgentBootstrapDelivery::Kubeconfig => self.apply_kubeconfig(client, cluster).await,
+        }
+    }
+
+    /// Stores the manifests in a `ClusterResourceSet` secret, and selects the CAPI cluster
+    /// in a `ClusterResourceSet`, so CAPI applies the manifests in the workload cluster.
+    async fn apply_resource_set(
+        &self,
+        client: Client,
+        cluster: &Cluster,
+    ) -> AgentBootstrapResult<()> {
+        let name = resource_set_name(cluster);
+        let namespace = cluster.namespace().unwrap_or_default();
+        let owner_references: Vec<_> = cluster.owner_ref(&()).into_iter().collect();
+        let pp = PatchParams::apply("addon-provider-fleet").force();
+
+        let manifests = self
+            .manifests()
+            .iter()
+            .map(serde_yaml::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("---\n");
+        let secret = json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {
+                "name": name,
+                "namespace": namespace,
+                "labels": {AGENT_BOOTSTRAP_LABEL: cluster.name_any()},
+                "ownerReferences": owner_references,
+            },
+            "type": "addons.cluster.x-k8s.io/resource-set",
+            "stringData": {MANIFESTS_KEY: manifests},
+        });
+        let secrets = Api::<Secret>::namespaced(client.clone(), &namespace);
+        let existing = secrets
+            .get_opt(&name)
+            .await
+            .map_err(AgentBootstrapError::Lookup)?;
+        if existing.is_some_and(|existing| !owned(&existing, cluster)) {
+            return Err(AgentBootstrapError::UnownedSecret(name));
+        }
+        secrets
+            .patch(&name, &pp, &Patch::Apply(&secret))
+            .await
+            .map_err(AgentBootstrapError::Apply)?;
+
+        let resource_set = json!({
+            "apiVersion": "addons.cluster.x-k8s.io/v1beta1",
+            "kind": "ClusterResourceSet",
+            "metadata": {
+                "name": name,
+                "namespace": namespace,
+                "ownerReferences": owner_references,
+            },
+            "spec": {
+                "clusterSelector": {"matchLabels": {AGENT_BOOTSTRAP_LABEL: cluster.name_any()}},
+                "resources": [{"kind": "Secret", "name": name}],
+                "strategy": "Reconcile",
+            },
+        });
+        Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, &resource_set_api())
+            .patch(&name, &pp, &Patch::Apply(&resource_set))
+            .await
+            .map_err(AgentBootstrapError::Apply)?;
+
+        Cluster::get_api(client, &namespace)
+            .patch_metadata(
+                &cluster.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(json!({
+                    "metadata": {"labels": {AGENT_BOOTSTRAP_LABEL: cluster.name_any()}}
+                })),
+            )
+            .await
+            .map_err(AgentBootstrapError::Apply)?;
+
+        debug!("Applied agent bootstrap ClusterResourceSet {name}");
+        Ok(())
+    }
+
+    /// Applies the manifests in the workload cluster, using the CAPI kubeconfig secret.
+    async fn apply_kubeconfig(
+        &self,
+        client: Client,
+        cluster: &Cluster,
+    ) -> AgentBootstrapResult<()> {
+        let secret_name = format!("{}-kubeconfig", cluster.name_any());
+        let secret = Api::<Secret>::namespaced(client, &cluster.namespace().unwrap_or_default())
+            .get(&secret_name)
+            .await
+            .map_err(AgentBootstrapError::Lookup)?;
+        let kubeconfig = secret
+            .data
+            .as_ref()
+            .and_then(|data| data.get("value"))
+            .ok_or_else(|| AgentBootstrapError::MissingKubeconfig(secret_name.clone()))?;
+        let kubeconfig = Kubeconfig::from_yaml(&String::from_utf8_lossy(&kubeconfig.0))?;
+        let config =
+            kube::Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default()).await?;
+        let workload = Client::try_from(config).map_err(AgentBootstrapError::Apply)?;
+
+        let pp = PatchParams::apply("addon-provider-fleet").force();
+        for manifest in self.manifests() {
+            let object: DynamicObject = serde_json::from_value(manifest)?;
+            let types = object.types.clone().unwrap_or_default();
+            let (group, version) = types
+                .api_version
+                .split_once('/')
+                .unwrap_or(("", &types.api_version));
+            let resource =
+                ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, &types.kind));
+            let api = match object.namespace() {
+                Some(namespace) => {
+                    Api::<DynamicObject>::namespaced_with(workload.clone(), &namespace, &resource)
+                }
+                None => Api::<DynamicObject>::all_with(workload.clone(), &resource),
+            };
+            api.patch(&object.name_any(), &pp, &Patch::Apply(&object))
+                .await
+                .map_err(AgentBootstrapError::Apply)?;
+        }
+
+        debug!("Applied agent bootstrap manifests in the workload cluster");
+        Ok(())
+    }
+}
+
+/// Removes the agent bootstrap `ClusterResourceSet` and its secret, once the agent registered.
+/// A `ClusterResourceSet` with the `Reconcile` strategy would otherwise restore the bootstrap
+/// secret with the removed token.
+///
+/// Only clusters labeled for the `ClusterResourceSet` delivery have objects to remove. The label
+/// is dropped from the CAPI cluster last, so an interrupted removal is retried.
+pub(crate) async fn remove(client: Client, cluster: &Cluster) -> AgentBootstrapResult<()> {
+    if !cluster.labels().contains_key(AGENT_BOOTSTRAP_LABEL) {
+        return Ok(());
+    }
+
+    let name = resource_set_name(cluster);
+    let namespace = cluster.namespace().unwrap_or_default();
+
+    let resource_set =
+        Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, &resource_set_api());
+    ignore_missing(resource_set.delete(&name, &DeleteParams::default()).await)?;
+
+    let secrets = Api::<Secret>::namespaced(client.clone(), &namespace);
+    let secret = secrets
+        .get_opt(&name)
+        .await
+        .map_err(AgentBootstrapError::Lookup)?;
+    if secret.is_some_and(|secret| owned(&secret, cluster)) {
+        ignore_missing(secrets.delete(&name, &DeleteParams::default()).await)?;
+    }
+
+    Cluster::get_api(client, &namespace)
+        .patch_metadata(
+            &cluster.name_any(),
+            &PatchParams::default(),
+            &Patch::Merge(json!({
+                "metadata": {"labels": {AGENT_BOOTSTRAP_LABEL: null}}
+            })),
+        )
+        .await
+        .map_err(AgentBootstrapError::Delete)?;
+
+    info!("Removed agent bootstrap ClusterResourceSet {name}");
+    Ok(())
+}
+
+/// Returns whether the secret was created for the agent bootstrap of the cluster.
+/// Secrets without the bootstrap label and owner reference are never modified.
+fn owned(secret: &Secret, cluster: &Cluster) -> bool {
+    let labeled = secret.labels().get(AGENT_BOOTSTRAP_LABEL) == Some(&cluster.name_any());
+    let uid = cluster.uid();
+    labeled
+        && secret
+            .owner_references()
+            .iter()
+            .any(|owner| Some(&owner.uid) == uid.as_ref())
+}
+
+/// Returns the configured agent image, or the image of the installed Fleet version.
+fn agent_image(config: &FleetAddonConfig, bootstrap: Option<&AgentBootstrap>) -> Option<String> {
+    if let Some(image) = bootstrap.and_then(|bootstrap| bootstrap.image.clone()) {
+        return Some(image);
+    }
+
+    let version = config.status.as_ref()?.installed_version.as_ref()?;
+    let version = version.strip_prefix('v').unwrap_or(version);
+    Some(format!("rancher/fleet-agent:v{version}"))
+}
+
+fn resource_set_name(cluster: &Cluster) -> String {
+    format!("{}-fleet-agent", cluster.name_any())
+}
+
+fn resource_set_api() -> ApiResource {
+    ApiResource::from_gvk(&GroupVersionKind::gvk(
+        "addons.cluster.x-k8s.io",
+        "v1beta1",
+        "ClusterResourceSet",
+    ))
+}
+
+/// Treats missing objects as already removed.
+fn ignore_missing<T>(result: kube::Result<T>) -> AgentBootstrapResult<()> {
+    match result {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(AgentBootstrapError::Delete(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::prelude::*;
+    use k8s_openapi::api::core::v1::Secret;
+    use serde_json::json;
+
+    use crate::api::capi_cluster::Cluster;
+
+    use super::{AGENT_BOOTSTRAP_LABEL, AgentRegistration, BOOTSTRAP_SECRET, owned};
+
+    #[test]
+    fn test_agent_manifests() {
+        let registration = AgentRegistration {
+            namespace: "fleet-addon-agent".into(),
+            client_id: "client".into(),
+            image: "rancher/fleet-agent:v0.12.0".into(),
+            token: "secret-token".into(),
+            api_server_url: "https://fleet:6443".into(),
+            api_server_ca: BASE64_STANDARD.encode("ca"),
+            system_registration_namespace: "cattle-fleet-clusters-system".into(),
+            cluster_namespace: "default".into(),
+            host_network: true,
+            tolerations: json!([]),
+        };
+
+        let manifests = registration.manifests();
+        let kinds: Vec<_> = manifests.iter().map(|m| m["kind"].clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "Namespace",
+                "Secret",
+                "ConfigMap",
+                "ServiceAccount",
+                "ClusterRoleBinding",
+                "Deployment"
+            ]
+        );
+
+        let secret = &manifests[1];
+        assert_eq!(secret["metadata"]["name"], BOOTSTRAP_SECRET);
+        assert_eq!(
+            secret["data"]["token"],
+            BASE64_STANDARD.encode("secret-token")
+        );
+        assert_eq!(secret["data"]["apiServerCA"], BASE64_STANDARD.encode("ca"));
+
+        let config = manifests[2]["data"]["config"].as_str().unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(config).unwrap()["clientID"],
+            "client"
+        );
+
+        let deployment = &manifests[5];
+        assert_eq!(
+            deployment["spec"]["template"]["spec"]["containers"][0]["image"],
+            "rancher/fleet-agent:v0.12.0"
+        );
+        assert!(
+            manifests
+                .iter()
+                .filter(|m| m["kind"] != "Namespace" && m["kind"] != "ClusterRoleBinding")
+                .all(|m| m["metadata"]["namespace"] == "fleet-addon-agent")
+        );
+    }
+
+    #[test]
+    fn test_owned_secret() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default", "uid": "cluster-uid"},
+            "spec": {},
+        }))
+        .unwrap();
+        let secret = |labels: serde_json::Value, uid: &str| -> Secret {
+            serde_json::from_value(json!({
+                "metadata": {
+                    "name": "test-fleet-agent",
+                    "namespace": "default",
+                    "labels": labels,
+                    "ownerReferences": [{
+                        "apiVersion": "cluster.x-k8s.io/v1beta1",
+                        "kind": "Cluster",
+                        "name": "test",
+                        "uid": uid,
+                    }],
+                },
+            }))
+            .unwrap()
+        };
+
+        let labels = json!({AGENT_BOOTSTRAP_LABEL: "test"});
+        assert!(owned(&secret(labels.clone(), "cluster-uid"), &cluster));
+        assert!(!owned(&secret(labels, "other-uid"), &cluster));
+        assert!(!owned(&secret(json!({}), "cluster-uid"), &cluster));
+        assert!(!owned(
+            &secret(json!({AGENT_BOOTSTRAP_LABEL: "other"}), "cluster-uid"),
+            &cluster
+        ));
+    }
+}
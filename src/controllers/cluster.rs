@@ -1,21 +1,33 @@
 use crate::api::bundle_namespace_mapping::BundleNamespaceMapping;
-use crate::api::capi_cluster::{Cluster, FLEET_WORKSPACE_ANNOTATION};
+use crate::api::capi_cluster::{Cluster, FLEET_WORKSPACE_ANNOTATION, split_api_version};
 
-use crate::api::fleet_addon_config::FleetAddonConfig;
+use crate::api::fleet_addon_config::{
+    ClusterConfig, FleetAddonConfig, TemplateSource, TemplateValuesConfig, render_placeholders,
+};
+use crate::api::fleet_bundle_deployment::BundleDeployment;
 use crate::api::fleet_cluster::{self};
 
 #[cfg(feature = "agent-initiated")]
 use crate::api::fleet_cluster_registration_token::ClusterRegistrationToken;
 use crate::api::fleet_clustergroup::ClusterGroup;
 use crate::controllers::addon_config::to_dynamic_event;
+#[cfg(feature = "agent-initiated")]
+use crate::controllers::agent_bootstrap::{self, AgentRegistration};
 use crate::controllers::controller::GetApi;
+use chrono::{SecondsFormat, Utc};
+use fleet_api_rs::fleet_cluster::ClusterAgentTolerations;
 use futures::StreamExt as _;
-use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::api::core::v1::{Namespace, ObjectReference};
 use kube::api::{
-    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, PatchParams,
+    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, ObjectMeta,
+    PartialObjectMeta, PatchParams, TypeMeta,
 };
 
 use kube::client::scope;
+use kube::error::DiscoveryError;
+use kube::runtime::events::{Event, EventType};
+use kube::runtime::metadata_watcher;
+use kube::runtime::reflector::ObjectRef;
 use kube::runtime::watcher::{self, Config};
 use kube::{Api, Client};
 use kube::{
@@ -25,94 +37,101 @@ use kube::{
 };
 use serde::Serialize;
 use serde_json::{Value, json};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
 
 use super::controller::{
-    Context, FleetBundle, FleetController, fetch_config, get_or_create, patch,
+    Context, FLEET_FINALIZER, FleetBundle, FleetController, fetch_namespace_config, get_or_create,
+    patch, update_conditions,
+};
+use super::{
+    BundleResult, ClusterOverrideError, ClusterOverrideResult, ClusterSyncError, ClusterSyncResult,
+    TemplateSourceError, TemplateSourceResult,
 };
-use super::{BundleResult, ClusterSyncError, ClusterSyncResult};
 
 pub static CONTROLPLANE_INITIALIZED_CONDITION: &str = "ControlPlaneInitialized";
+pub static FLEET_ADDONS_READY_CONDITION: &str = "FleetAddonsReady";
+pub static TEMPLATE_VALUES_RESOLVED_CONDITION: &str = "FleetTemplateValuesResolved";
+/// Interval for checking a pending registration token, before the Fleet agent registers.
+#[cfg(feature = "agent-initiated")]
+const REGISTRATION_TOKEN_RECHECK: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub static BUNDLE_DEPLOYMENT_CLUSTER_LABEL: &str = "fleet.cattle.io/cluster";
+pub static BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL: &str = "fleet.cattle.io/cluster-namespace";
+
+pub static IMPORT_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/import";
+pub static AGENT_NAMESPACE_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/agent-namespace";
+pub static HOST_NETWORK_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/host-network";
+pub static AGENT_TOLERATIONS_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/agent-tolerations";
 
 pub struct FleetClusterBundle {
+    cluster: Cluster,
     namespace: Namespace,
     template_sources: TemplateSources,
     fleet: fleet_cluster::Cluster,
     fleet_group: Option<ClusterGroup>,
+    rule_groups: Vec<ClusterGroup>,
     mapping: Option<BundleNamespaceMapping>,
     #[cfg(feature = "agent-initiated")]
     cluster_registration_token: Option<ClusterRegistrationToken>,
     config: FleetAddonConfig,
+    import: bool,
 }
 
-pub struct TemplateSources(Cluster);
+pub struct TemplateSources {
+    cluster: Cluster,
+    sources: Vec<TemplateSource>,
+    config: TemplateValuesConfig,
+}
 
 #[derive(Serialize)]
 struct TemplateValues {
     #[serde(rename = "Cluster")]
     cluster: Cluster,
-    #[serde(rename = "ControlPlane")]
-    control_plane: DynamicObject,
-    #[serde(rename = "InfrastructureCluster")]
-    infrastructure_cluster: DynamicObject,
+    #[serde(rename = "ControlPlane", skip_serializing_if = "Option::is_none")]
+    control_plane: Option<DynamicObject>,
+    #[serde(
+        rename = "InfrastructureCluster",
+        skip_serializing_if = "Option::is_none"
+    )]
+    infrastructure_cluster: Option<DynamicObject>,
 }
 
 impl TemplateSources {
-    fn new(cluster: &Cluster) -> Self {
-        TemplateSources(cluster.clone())
+    fn new(cluster: &Cluster, config: Option<&ClusterConfig>) -> Self {
+        TemplateSources {
+            cluster: cluster.clone(),
+            sources: config
+                .and_then(|config| config.template_sources.clone())
+                .unwrap_or_default(),
+            config: config
+                .and_then(|config| config.template_values.clone())
+                .unwrap_or_default(),
+        }
     }
 
-    async fn resolve(&self, client: Client) -> Option<Value> {
+    async fn resolve(&self, client: Client) -> TemplateSourceResult<Value> {
         // We need to remove all dynamic or unnessesary values from these resources
-        let mut cluster = self.0.clone();
+        let mut cluster = self.cluster.clone();
 
         cluster.status = None;
         cluster.meta_mut().managed_fields = None;
         cluster.meta_mut().resource_version = None;
 
-        let reference = self.0.spec.proxy.control_plane_ref.as_ref()?;
-        let api_version = reference.api_version.as_ref()?;
-        let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
-        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(
-            group,
-            version,
-            reference.kind.as_ref()?,
-        ));
-        let api = Api::<DynamicObject>::namespaced_with(
+        let control_plane = resolve_reference(
             client.clone(),
-            reference.namespace.as_ref()?,
-            &resource,
-        );
-        let mut control_plane = api.get(reference.name.as_ref()?).await.ok()?;
-
-        if let Some(data_object) = control_plane.data.as_object_mut() {
-            data_object.remove("status");
-        }
-        control_plane.meta_mut().managed_fields = None;
-        control_plane.meta_mut().resource_version = None;
-
-        let infra_reference = self.0.spec.proxy.infrastructure_ref.as_ref()?;
-        let api_version = infra_reference.api_version.as_ref()?;
-        let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
-        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(
-            group,
-            version,
-            infra_reference.kind.as_ref()?,
-        ));
-        let api = Api::<DynamicObject>::namespaced_with(
+            "ControlPlane",
+            self.cluster.control_plane_reference(),
+        )
+        .await?;
+        let infrastructure_cluster = resolve_reference(
             client.clone(),
-            infra_reference.namespace.as_ref()?,
-            &resource,
-        );
-        let mut infrastructure_cluster = api.get(infra_reference.name.as_ref()?).await.ok()?;
-
-        if let Some(data_object) = infrastructure_cluster.data.as_object_mut() {
-            data_object.remove("status");
-        }
-        infrastructure_cluster.meta_mut().managed_fields = None;
-        infrastructure_cluster.meta_mut().resource_version = None;
+            "InfrastructureCluster",
+            self.cluster.infrastructure_reference(),
+        )
+        .await?;
 
         let values = TemplateValues {
             cluster,
@@ -120,20 +139,296 @@ impl TemplateSources {
             infrastructure_cluster,
         };
 
-        serde_json::to_value(values).ok()
+        let mut values = serde_json::to_value(values)?;
+        if let Some(values) = values.as_object_mut() {
+            for source in &self.sources {
+                let value = self.resolve_source(client.clone(), source).await?;
+                // Built-in values take precedence over sources with the same key
+                values.entry(source.key.clone()).or_insert(value);
+            }
+
+            for (key, value) in values.iter_mut() {
+                *value = self.config.select(key, value.take());
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Resolves a single template source to the referenced object,
+    /// or the list of objects matching the selector if the name is not set.
+    async fn resolve_source(
+        &self,
+        client: Client,
+        source: &TemplateSource,
+    ) -> TemplateSourceResult<Value> {
+        let lookup = |placeholder: &str| self.cluster.template_placeholder(placeholder);
+        let (group, version) = source
+            .api_version
+            .split_once('/')
+            .unwrap_or(("", &source.api_version));
+        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, &source.kind));
+        let namespace = match &source.namespace {
+            Some(namespace) => render_placeholders(namespace, lookup),
+            None => self.cluster.namespace().unwrap_or_default(),
+        };
+        let api = Api::<DynamicObject>::namespaced_with(client, &namespace, &resource);
+        let lookup_error = |e| TemplateSourceError::Lookup(source.key.clone(), e);
+
+        if let Some(name) = &source.name {
+            let mut object = api
+                .get(&render_placeholders(name, lookup))
+                .await
+                .map_err(lookup_error)?;
+            strip_dynamic_fields(&mut object);
+            select_keys(source, &mut object);
+            return Ok(serde_json::to_value(object)?);
+        }
+
+        let selector = source
+            .render_selector(lookup)
+            .map_err(|e| TemplateSourceError::Selector(source.key.clone(), e))?;
+        let objects: Vec<Value> = api
+            .list(&ListParams::default().labels_from(&selector))
+            .await
+            .map_err(lookup_error)?
+            .into_iter()
+            .map(|mut object| {
+                strip_dynamic_fields(&mut object);
+                select_keys(source, &mut object);
+                serde_json::to_value(object)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Value::Array(objects))
+    }
+}
+
+/// Fetches the object referenced by the cluster under the template values key.
+/// Clusters without the reference have nothing to resolve.
+async fn resolve_reference(
+    client: Client,
+    key: &str,
+    reference: Option<ObjectReference>,
+) -> TemplateSourceResult<Option<DynamicObject>> {
+    let Some(reference) = reference else {
+        return Ok(None);
+    };
+    let ObjectReference {
+        api_version: Some(api_version),
+        kind: Some(kind),
+        name: Some(name),
+        namespace: Some(namespace),
+        ..
+    } = reference
+    else {
+        return Err(TemplateSourceError::IncompleteReference(key.to_string()));
+    };
+
+    let resource = reference_resource(&client, &api_version, &kind)
+        .await
+        .map_err(|e| TemplateSourceError::Lookup(key.to_string(), e))?;
+    let mut object = Api::<DynamicObject>::namespaced_with(client, &namespace, &resource)
+        .get(&name)
+        .await
+        .map_err(|e| TemplateSourceError::Lookup(key.to_string(), e))?;
+
+    strip_dynamic_fields(&mut object);
+    Ok(Some(object))
+}
+
+/// Preferred API resources of versionless referenced kinds by group and kind. Discovery runs
+/// once per kind, and is shared by the template values resolution and the reference watches.
+static DISCOVERED_RESOURCES: LazyLock<RwLock<HashMap<(String, String), ApiResource>>> =
+    LazyLock::new(RwLock::default);
+
+/// Returns the API resource of a referenced kind. References without a version,
+/// which are used by CAPI `v1beta2`, are resolved to the preferred version of the group.
+async fn reference_resource(
+    client: &Client,
+    api_version: &str,
+    kind: &str,
+) -> kube::Result<ApiResource> {
+    let (group, version) = split_api_version(api_version);
+    if let Some(version) = version {
+        return Ok(ApiResource::from_gvk(&GroupVersionKind::gvk(
+            group, version, kind,
+        )));
+    }
+
+    let key = (group.to_string(), kind.to_string());
+    if let Some(resource) = DISCOVERED_RESOURCES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+    {
+        return Ok(resource.clone());
+    }
+
+    let resource = kube::discovery::oneshot::group(client, group)
+        .await?
+        .recommended_kind(kind)
+        .map(|(resource, _)| resource)
+        .ok_or_else(|| kube::Error::Discovery(DiscoveryError::MissingKind(kind.to_string())))?;
+    DISCOVERED_RESOURCES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key, resource.clone());
+
+    Ok(resource)
+}
+
+/// Converts a metadata event of a referenced object into a dynamic object event.
+/// Reference watches only trigger the cluster reconcile, so the object data is not needed.
+fn reference_event(
+    event: watcher::Event<PartialObjectMeta<DynamicObject>>,
+    types: &TypeMeta,
+) -> watcher::Event<DynamicObject> {
+    let object = |meta: PartialObjectMeta<DynamicObject>| DynamicObject {
+        types: Some(types.clone()),
+        metadata: meta.metadata,
+        data: Value::Null,
+    };
+    match event {
+        watcher::Event::Apply(o) => watcher::Event::Apply(object(o)),
+        watcher::Event::Delete(o) => watcher::Event::Delete(object(o)),
+        watcher::Event::InitApply(o) => watcher::Event::InitApply(object(o)),
+        watcher::Event::Init => watcher::Event::Init,
+        watcher::Event::InitDone => watcher::Event::InitDone,
+    }
+}
+
+/// Removes the registration token, ignoring tokens which are already removed.
+#[cfg(feature = "agent-initiated")]
+async fn delete_registration_token(
+    api: &Api<ClusterRegistrationToken>,
+    name: &str,
+) -> ClusterSyncResult<()> {
+    match api.delete(name, &DeleteParams::default()).await {
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        e => e
+            .map(|_| ())
+            .map_err(ClusterSyncError::RegistrationTokenError),
+    }
+}
+
+/// Removes status and server-managed metadata, which change with every update of the object.
+fn strip_dynamic_fields(object: &mut DynamicObject) {
+    if let Some(data_object) = object.data.as_object_mut() {
+        data_object.remove("status");
+    }
+    object.meta_mut().managed_fields = None;
+    object.meta_mut().resource_version = None;
+}
+
+/// Limits the `data` of a `ConfigMap` or `Secret` to the keys of the source.
+/// A `Secret` only keeps its name and namespace, and exposes no data without keys.
+fn select_keys(source: &TemplateSource, object: &mut DynamicObject) {
+    if source.is_secret() {
+        object.metadata = ObjectMeta {
+            name: object.metadata.name.take(),
+            namespace: object.metadata.namespace.take(),
+            ..Default::default()
+        };
+        let data = object.data.get_mut("data").map(Value::take);
+        object.data = json!({ "data": data.unwrap_or_default() });
+    }
+
+    let keys = match &source.keys {
+        Some(keys) => keys.as_slice(),
+        None if source.is_secret() => &[],
+        None => return,
+    };
+    for field in ["data", "binaryData"] {
+        if let Some(data) = object.data.get_mut(field).and_then(Value::as_object_mut) {
+            data.retain(|key, _| keys.contains(key));
+        }
+    }
+}
+
+/// Index of imported Fleet clusters to the CAPI clusters they are created from.
+/// Fleet cluster names differ from CAPI cluster names when a naming strategy is configured.
+#[derive(Clone, Default)]
+pub struct FleetClusterIndex(
+    Arc<RwLock<HashMap<ObjectRef<fleet_cluster::Cluster>, ObjectRef<Cluster>>>>,
+);
+
+impl FleetClusterIndex {
+    /// Records the Fleet cluster of the CAPI cluster, replacing a previous Fleet cluster name.
+    pub(crate) fn insert(&self, fleet: &fleet_cluster::Cluster, cluster: &Cluster) {
+        let cluster = ObjectRef::from_obj(cluster);
+        let mut index = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        index.retain(|_, c| *c != cluster);
+        index.insert(ObjectRef::from_obj(fleet), cluster);
+    }
+
+    /// Removes the Fleet cluster of the CAPI cluster from the index.
+    pub(crate) fn remove(&self, cluster: &Cluster) {
+        let cluster = ObjectRef::from_obj(cluster);
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|_, c| *c != cluster);
+    }
+
+    /// Returns the CAPI cluster of the Fleet cluster with the given name and namespace.
+    #[must_use]
+    pub fn get(&self, name: &str, namespace: Option<&str>) -> Option<ObjectRef<Cluster>> {
+        let mut fleet = ObjectRef::new(name);
+        fleet.namespace = namespace.map(Into::into);
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&fleet)
+            .cloned()
     }
 }
 
 impl FleetBundle for FleetClusterBundle {
     #[allow(refining_impl_trait)]
     async fn sync(&mut self, ctx: Arc<Context>) -> ClusterSyncResult<Action> {
-        let cluster = &mut self.fleet;
+        if !self.import {
+            ctx.fleet_clusters.remove(&self.cluster);
+            return self.remove_import(ctx).await;
+        }
+
+        ctx.fleet_clusters.insert(&self.fleet, &self.cluster);
 
-        if let Some(template) = self.template_sources.resolve(ctx.client.clone()).await {
-            let template = serde_json::from_value(template)?;
-            cluster.spec.template_values = Some(template);
+        if self.config.cluster_patch_enabled() {
+            self.remove_stale_metadata(ctx.client.clone()).await?;
         }
 
+        self.cluster
+            .add_reference_dynamic_watches(ctx.clone())
+            .await;
+
+        let template_condition = match self.template_sources.resolve(ctx.client.clone()).await {
+            Ok(template) => {
+                let size = serde_json::to_vec(&template)?.len();
+                let max_size = self.template_sources.config.max_size();
+                if size > max_size {
+                    let message = format!(
+                        "Template values of {size} bytes exceed the limit of {max_size} bytes, previous values are kept"
+                    );
+                    self.keep_template_values(ctx.clone(), "TemplateValuesTooLarge", &message)
+                        .await?;
+                    template_values_condition(Some(("TemplateValuesTooLarge", message)))
+                } else {
+                    self.fleet.spec.template_values = Some(serde_json::from_value(template)?);
+                    template_values_condition(None)
+                }
+            }
+            Err(e) => {
+                ctx.metrics.template_failure(&self.cluster, &e);
+                let message = format!("{e}, previous values are kept");
+                self.keep_template_values(ctx.clone(), "TemplateResolutionFailed", &message)
+                    .await?;
+                template_values_condition(Some(("TemplateResolutionFailed", message)))
+            }
+        };
+
+        let cluster = &mut self.fleet;
+
         if let Some(mapping) = self.mapping.as_mut() {
             if self.config.cluster_patch_enabled() {
                 let cluster_name = cluster.name_any();
@@ -165,9 +460,9 @@ impl FleetBundle for FleetClusterBundle {
         };
 
         #[cfg(feature = "agent-initiated")]
-        if let Some(cluster_registration_token) = self.cluster_registration_token.as_ref() {
-            get_or_create(ctx.clone(), cluster_registration_token).await?;
-        }
+        let action = self.sync_registration_token(ctx.clone()).await?;
+        #[cfg(not(feature = "agent-initiated"))]
+        let action = Action::await_change();
 
         if let Some(group) = self.fleet_group.as_mut() {
             let cluster_name = self.fleet.name_any();
@@ -182,6 +477,21 @@ impl FleetBundle for FleetClusterBundle {
             }
         }
 
+        for group in &mut self.rule_groups {
+            let cluster_name = self.fleet.name_any();
+            if self.config.cluster_patch_enabled() {
+                patch(
+                    ctx.clone(),
+                    group,
+                    &PatchParams::apply(&format!("cluster-{cluster_name}-addon-provider-fleet")),
+                )
+                .await
+                .map_err(ClusterSyncError::GroupPatchError)?;
+            } else {
+                get_or_create(ctx.clone(), group).await?;
+            }
+        }
+
         // Ensure the fleet workspace annotation is present.
         patch(
             ctx.clone(),
@@ -196,10 +506,20 @@ impl FleetBundle for FleetClusterBundle {
             self.fleet.get_namespace()
         );
 
-        Ok(Action::await_change())
+        let summary = self.bundle_summary(ctx.client.clone()).await?;
+        self.cluster
+            .update_conditions(
+                ctx.client.clone(),
+                vec![summary.to_condition(), template_condition],
+            )
+            .await?;
+
+        Ok(action)
     }
 
     async fn cleanup(&mut self, ctx: Arc<Context>) -> Result<Action, super::SyncError> {
+        ctx.fleet_clusters.remove(&self.cluster);
+
         if let Some(mapping) = self.mapping.as_ref() {
             let ns = mapping.namespace();
             let other_clusters = ctx
@@ -212,7 +532,7 @@ impl FleetBundle for FleetClusterBundle {
 
             let referencing_cluster = other_clusters.iter().find(|c| {
                 c.cluster_class_namespace() == ns.as_deref()
-                    && c.name_any() != self.fleet.name_any()
+                    && c.name_any() != self.cluster.name_any()
                     && c.metadata.deletion_timestamp.is_none()
             });
 
@@ -229,7 +549,7 @@ impl FleetBundle for FleetClusterBundle {
         let other_clusters = Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
             .list(
                 &ListParams::default()
-                    .fields(&format!("metadata.name!={}", self.fleet.name_any()))
+                    .fields(&format!("metadata.name!={}", self.cluster.name_any()))
                     .limit(1),
             )
             .await?;
@@ -259,30 +579,440 @@ impl FleetBundle for FleetClusterBundle {
     }
 }
 
+impl FleetClusterBundle {
+    /// Keeps the template values of the existing Fleet cluster when the resolved values
+    /// can't be written, and publishes a warning on the CAPI cluster.
+    async fn keep_template_values(
+        &mut self,
+        ctx: Arc<Context>,
+        reason: &str,
+        message: &str,
+    ) -> ClusterSyncResult<()> {
+        warn!("{message}");
+
+        let existing =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .get_opt(&self.fleet.name_any())
+                .await
+                .map_err(ClusterSyncError::TemplateValuesError)?;
+        self.fleet.spec.template_values = existing.and_then(|cluster| cluster.spec.template_values);
+
+        match ctx
+            .diagnostics
+            .read()
+            .await
+            .recorder(ctx.client.clone())
+            .publish(
+                &Event {
+                    type_: EventType::Warning,
+                    reason: reason.into(),
+                    note: Some(message.into()),
+                    action: "Templating".into(),
+                    secondary: Some(self.fleet.object_ref(&())),
+                },
+                &self.cluster.object_ref(&()),
+            )
+            .await
+        {
+            // Ignore forbidden errors on namespace deletion
+            Err(kube::Error::Api(e)) if &e.reason == "Forbidden" => Ok(()),
+            e => e.map_err(ClusterSyncError::TemplateValuesError),
+        }
+    }
+
+    /// Removes the Fleet cluster and the objects created for it once the CAPI cluster opts out
+    /// of the import with the import annotation, and drops the finalizer from the CAPI cluster.
+    /// Clusters which were never imported have nothing to remove.
+    async fn remove_import(&mut self, ctx: Arc<Context>) -> ClusterSyncResult<Action> {
+        let api = fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        if api
+            .get_metadata_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ImportRemovalError)?
+            .is_some()
+        {
+            api.delete(&self.fleet.name_any(), &DeleteParams::default())
+                .await
+                .map_err(ClusterSyncError::ImportRemovalError)?;
+            info!("Removed Fleet cluster of the cluster opted out of the import");
+        }
+
+        self.remove_owned_groups(ctx.clone()).await?;
+
+        #[cfg(feature = "agent-initiated")]
+        if let Some(token) = self.cluster_registration_token.as_ref() {
+            let api = ClusterRegistrationToken::get_api(ctx.client.clone(), token.get_namespace());
+            delete_registration_token(&api, &token.name_any()).await?;
+            agent_bootstrap::remove(ctx.client.clone(), &self.cluster).await?;
+        }
+
+        if self
+            .cluster
+            .finalizers()
+            .iter()
+            .any(|f| f == FLEET_FINALIZER)
+        {
+            let finalizers: Vec<_> = self
+                .cluster
+                .finalizers()
+                .iter()
+                .filter(|f| *f != FLEET_FINALIZER)
+                .collect();
+            Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .patch_metadata(
+                    &self.cluster.name_any(),
+                    &PatchParams::default(),
+                    &Patch::Merge(json!({"metadata": {"finalizers": finalizers}})),
+                )
+                .await
+                .map_err(ClusterSyncError::ImportRemovalError)?;
+            debug!("Removed finalizer from the cluster opted out of the import");
+        }
+
+        Ok(Action::await_change())
+    }
+
+    /// Removes the cluster owner reference from the class and rule `ClusterGroups` in the
+    /// cluster namespace. Groups without other member Fleet clusters are removed.
+    async fn remove_owned_groups(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let Some(uid) = self.cluster.uid() else {
+            return Ok(());
+        };
+
+        let api = ClusterGroup::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let groups = api
+            .list(&ListParams::default())
+            .await
+            .map_err(ClusterSyncError::ImportRemovalError)?;
+        for group in groups {
+            let owners = group.owner_references();
+            if !owners.iter().any(|owner| owner.uid == uid) {
+                continue;
+            }
+
+            let selector = group
+                .spec
+                .selector
+                .iter()
+                .flat_map(|selector| selector.match_labels.iter().flatten())
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            let members =
+                fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                    .list_metadata(&ListParams::default().labels(&selector))
+                    .await
+                    .map_err(ClusterSyncError::ImportRemovalError)?;
+            let other_members = !selector.is_empty()
+                && members
+                    .iter()
+                    .any(|member| member.name_any() != self.fleet.name_any());
+
+            if other_members {
+                let owners: Vec<_> = owners.iter().filter(|owner| owner.uid != uid).collect();
+                api.patch_metadata(
+                    &group.name_any(),
+                    &PatchParams::default(),
+                    &Patch::Merge(json!({"metadata": {"ownerReferences": owners}})),
+                )
+                .await
+                .map_err(ClusterSyncError::ImportRemovalError)?;
+            } else {
+                match api
+                    .delete(&group.name_any(), &DeleteParams::default())
+                    .await
+                {
+                    Err(kube::Error::Api(e)) if e.code == 404 => {}
+                    e => {
+                        e.map_err(ClusterSyncError::ImportRemovalError)?;
+                    }
+                }
+                info!(
+                    "Removed ClusterGroup {} of the cluster opted out of the import",
+                    group.name_any()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keeps a valid `ClusterRegistrationToken` until the Fleet agent registers. Expired tokens,
+    /// or tokens with a changed TTL, are removed and re-created on the next reconcile.
+    /// While the token is valid, the agent bootstrap manifests are delivered to the workload
+    /// cluster, if configured. The token and the bootstrap objects are removed once the agent
+    /// reported back to the Fleet cluster.
+    #[cfg(feature = "agent-initiated")]
+    async fn sync_registration_token(&self, ctx: Arc<Context>) -> ClusterSyncResult<Action> {
+        let Some(token) = self.cluster_registration_token.as_ref() else {
+            return Ok(Action::await_change());
+        };
+
+        let fleet = fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::RegistrationTokenError)?;
+        let registered = fleet
+            .as_ref()
+            .is_some_and(fleet_cluster::Cluster::agent_registered);
+        let bootstrap = self
+            .config
+            .spec
+            .cluster
+            .as_ref()
+            .and_then(|config| config.agent_bootstrap.as_ref());
+
+        let api = ClusterRegistrationToken::get_api(ctx.client.clone(), token.get_namespace());
+        let existing = api
+            .get_opt(&token.name_any())
+            .await
+            .map_err(ClusterSyncError::RegistrationTokenError)?;
+
+        let now = Utc::now();
+        if registered {
+            if existing.is_some() {
+                delete_registration_token(&api, &token.name_any()).await?;
+                info!("Removed registration token, the agent is registered");
+            }
+            agent_bootstrap::remove(ctx.client.clone(), &self.cluster).await?;
+            return Ok(Action::await_change());
+        }
+
+        match existing {
+            Some(existing) if existing.expired(now) || existing.spec != token.spec => {
+                delete_registration_token(&api, &token.name_any()).await?;
+                info!("Removed expired registration token, re-creating");
+                Ok(Action::requeue(REGISTRATION_TOKEN_RECHECK))
+            }
+            Some(existing) => {
+                let registration = match (bootstrap, fleet.as_ref()) {
+                    (Some(_), Some(fleet)) => {
+                        AgentRegistration::fetch(ctx.client.clone(), &existing, fleet, &self.config)
+                            .await?
+                    }
+                    _ => None,
+                };
+                if let (Some(bootstrap), Some(registration)) = (bootstrap, registration.as_ref()) {
+                    registration
+                        .deliver(ctx.client.clone(), &self.cluster, bootstrap)
+                        .await?;
+                } else if bootstrap.is_some() {
+                    // The token secret is not populated yet
+                    return Ok(Action::requeue(REGISTRATION_TOKEN_RECHECK));
+                }
+
+                // Re-check at the expiration, unless the agent registers before
+                let requeue = existing
+                    .expires()
+                    .and_then(|expires| (expires - now).to_std().ok())
+                    .unwrap_or(REGISTRATION_TOKEN_RECHECK);
+                Ok(Action::requeue(requeue.max(REGISTRATION_TOKEN_RECHECK)))
+            }
+            None => {
+                get_or_create(ctx.clone(), token).await?;
+                Ok(Action::requeue(REGISTRATION_TOKEN_RECHECK))
+            }
+        }
+    }
+
+    /// Removes labels and annotations previously propagated to the Fleet cluster,
+    /// which were removed from the CAPI cluster since.
+    async fn remove_stale_metadata(&self, client: Client) -> ClusterSyncResult<()> {
+        let api = fleet_cluster::Cluster::get_api(client, self.fleet.get_namespace());
+        let Some(existing) = api
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::StaleMetadataError)?
+        else {
+            return Ok(());
+        };
+
+        let (labels, annotations) = self.fleet.stale_keys(&existing);
+        if labels.is_empty() && annotations.is_empty() {
+            return Ok(());
+        }
+
+        let removed = |keys: &[String]| -> serde_json::Map<String, Value> {
+            keys.iter().map(|key| (key.clone(), Value::Null)).collect()
+        };
+        let patch = json!({
+            "metadata": {
+                "labels": removed(&labels),
+                "annotations": removed(&annotations),
+            }
+        });
+        api.patch_metadata(
+            &self.fleet.name_any(),
+            &PatchParams::default(),
+            &Patch::Merge(&patch),
+        )
+        .await
+        .map_err(ClusterSyncError::StaleMetadataError)?;
+
+        debug!(
+            "Removed stale labels {labels:?} and annotations {annotations:?} from Fleet cluster"
+        );
+
+        Ok(())
+    }
+
+    /// Collects the state of all `BundleDeployment`s targeting the imported Fleet cluster.
+    async fn bundle_summary(&self, client: Client) -> ClusterSyncResult<BundleSummary> {
+        let selector = format!(
+            "{BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL}={},{BUNDLE_DEPLOYMENT_CLUSTER_LABEL}={}",
+            self.fleet.get_namespace(),
+            self.fleet.name_any()
+        );
+
+        let bundle_deployments = Api::<BundleDeployment>::all(client)
+            .list(&ListParams::default().labels(&selector))
+            .await
+            .map_err(ClusterSyncError::BundleDeploymentLookupError)?;
+
+        Ok(bundle_deployments.iter().collect())
+    }
+}
+
+/// `BundleSummary` aggregates the state of all `BundleDeployment`s targeting a Fleet cluster.
+#[derive(Default, Debug, PartialEq)]
+pub struct BundleSummary {
+    pub desired: usize,
+    pub ready: usize,
+    pub modified: usize,
+    pub non_ready: usize,
+}
+
+impl<'a> FromIterator<&'a BundleDeployment> for BundleSummary {
+    fn from_iter<T: IntoIterator<Item = &'a BundleDeployment>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::default(), |mut summary, bundle| {
+            summary.desired += 1;
+
+            let Some(status) = bundle.status.as_ref() else {
+                summary.non_ready += 1;
+                return summary;
+            };
+
+            let ready = status.ready.is_some_and(|ready| ready)
+                && status.non_ready_status.as_ref().is_none_or(Vec::is_empty);
+            let modified = status.non_modified.is_some_and(|non_modified| !non_modified)
+                || status.modified_status.as_ref().is_some_and(|m| !m.is_empty());
+
+            match (ready, modified) {
+                (false, _) => summary.non_ready += 1,
+                (true, true) => summary.modified += 1,
+                (true, false) => summary.ready += 1,
+            }
+
+            summary
+        })
+    }
+}
+
+impl BundleSummary {
+    /// Renders the summary as a CAPI `v1beta1` condition.
+    fn to_condition(&self) -> Value {
+        let Self {
+            desired,
+            ready,
+            modified,
+            non_ready,
+        } = self;
+        let message = format!(
+            "Bundles ready: {ready}/{desired}, modified: {modified}, non-ready: {non_ready}"
+        );
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        if non_ready + modified == 0 {
+            json!({
+                "type": FLEET_ADDONS_READY_CONDITION,
+                "status": "True",
+                "reason": "BundlesReady",
+                "message": message,
+                "lastTransitionTime": now,
+            })
+        } else {
+            json!({
+                "type": FLEET_ADDONS_READY_CONDITION,
+                "status": "False",
+                "severity": "Warning",
+                "reason": "BundlesNotReady",
+                "message": message,
+                "lastTransitionTime": now,
+            })
+        }
+    }
+}
+
+/// Renders the template values state as a CAPI `v1beta1` condition, with the reason
+/// and message of the failure if the values could not be written.
+fn template_values_condition(failure: Option<(&str, String)>) -> Value {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    match failure {
+        None => json!({
+            "type": TEMPLATE_VALUES_RESOLVED_CONDITION,
+            "status": "True",
+            "reason": "TemplateValuesResolved",
+            "lastTransitionTime": now,
+        }),
+        Some((reason, message)) => json!({
+            "type": TEMPLATE_VALUES_RESOLVED_CONDITION,
+            "status": "False",
+            "severity": "Warning",
+            "reason": reason,
+            "message": message,
+            "lastTransitionTime": now,
+        }),
+    }
+}
+
 impl FleetController for Cluster {
     type Bundle = FleetClusterBundle;
 
+    fn finalized(&self) -> bool {
+        // Opted out clusters only remove a previous import, which needs no finalizer
+        self.import_enabled().unwrap_or(true)
+    }
+
     async fn to_bundle(&self, ctx: Arc<Context>) -> BundleResult<Option<FleetClusterBundle>> {
-        let config = fetch_config(ctx.client.clone()).await?;
+        let mut config =
+            fetch_namespace_config(ctx.client.clone(), &self.namespace().unwrap_or_default())
+                .await?;
 
         if !config.cluster_operations_enabled() {
             return Ok(None);
         }
 
-        if self.cluster_ready().is_none() {
-            return Ok(None);
+        // Opted out clusters keep the bundle, so a previous import is removed
+        let import = self.import_enabled()?;
+        if import {
+            if let Some(cluster) = config.spec.cluster.as_mut() {
+                self.apply_annotation_overrides(cluster)?;
+            }
+
+            if self.cluster_ready().is_none() {
+                return Ok(None);
+            }
+
+            if !self.readiness_gates_met(config.spec.cluster.as_ref()) {
+                debug!("Cluster is waiting for readiness gate conditions");
+                return Ok(None);
+            }
         }
 
         Ok(Some(FleetClusterBundle {
-            template_sources: TemplateSources::new(self),
+            cluster: self.clone(),
+            template_sources: TemplateSources::new(self, config.spec.cluster.as_ref()),
             fleet: self.to_cluster(config.spec.cluster.as_ref()),
             fleet_group: self.to_group(config.spec.cluster.as_ref()),
+            rule_groups: self.to_rule_groups(config.spec.cluster.as_ref()),
             mapping: self.to_bundle_ns_mapping(config.spec.cluster.as_ref()),
             #[cfg(feature = "agent-initiated")]
             cluster_registration_token: self
                 .to_cluster_registration_token(config.spec.cluster.as_ref()),
             config,
             namespace: self.to_namespace(),
+            import,
         }))
     }
 }
@@ -299,6 +1029,88 @@ impl Cluster {
         ready_condition.or(cp_ready).map(|_| self)
     }
 
+    /// Checks that every configured readiness gate condition has the required status.
+    /// Conditions are looked up in `status.conditions` and `status.v1beta2.conditions`.
+    pub(crate) fn readiness_gates_met(&self, config: Option<&ClusterConfig>) -> bool {
+        let Some(gates) = config.and_then(|config| config.readiness_gates.as_ref()) else {
+            return true;
+        };
+
+        let status = serde_json::to_value(&self.status).unwrap_or_default();
+        let conditions: Vec<&Value> = ["/conditions", "/v1beta2/conditions"]
+            .into_iter()
+            .filter_map(|pointer| status.pointer(pointer)?.as_array())
+            .flatten()
+            .collect();
+
+        gates.iter().all(|gate| {
+            conditions.iter().any(|condition| {
+                condition["type"] == gate.condition_type.as_str()
+                    && condition["status"] == gate.status()
+            })
+        })
+    }
+
+    /// Checks the import opt-out annotation. Clusters are imported unless the annotation is set to `false`.
+    pub(crate) fn import_enabled(&self) -> ClusterOverrideResult<bool> {
+        self.bool_annotation(IMPORT_ANNOTATION)
+            .map(|import| import.unwrap_or(true))
+    }
+
+    /// Applies per-cluster agent settings from the `Cluster` annotations over the config.
+    /// Tolerations from the annotation are appended to the configured agent tolerations.
+    pub(crate) fn apply_annotation_overrides(
+        &self,
+        config: &mut ClusterConfig,
+    ) -> ClusterOverrideResult<()> {
+        let annotations = self.annotations();
+
+        if let Some(namespace) = annotations.get(AGENT_NAMESPACE_ANNOTATION) {
+            config.agent_namespace = Some(namespace.clone());
+        }
+
+        if let Some(host_network) = self.bool_annotation(HOST_NETWORK_ANNOTATION)? {
+            config.host_network = Some(host_network);
+        }
+
+        if let Some(tolerations) = annotations.get(AGENT_TOLERATIONS_ANNOTATION) {
+            let extra: Vec<ClusterAgentTolerations> =
+                serde_yaml::from_str(tolerations).map_err(|e| {
+                    ClusterOverrideError::Tolerations(AGENT_TOLERATIONS_ANNOTATION.into(), e)
+                })?;
+            let mut tolerations = config.agent_tolerations();
+            tolerations.extend(extra);
+            config.agent_tolerations = Some(tolerations);
+        }
+
+        Ok(())
+    }
+
+    fn bool_annotation(&self, annotation: &str) -> ClusterOverrideResult<Option<bool>> {
+        self.annotations()
+            .get(annotation)
+            .map(|value| value.parse())
+            .transpose()
+            .map_err(|e| ClusterOverrideError::Bool(annotation.into(), e))
+    }
+
+    /// Mirrors the `FleetAddonsReady` and `FleetTemplateValuesResolved` conditions
+    /// onto the `Cluster` status. The status is only patched when a condition changes.
+    async fn update_conditions(
+        &self,
+        client: Client,
+        desired: Vec<Value>,
+    ) -> ClusterSyncResult<()> {
+        if update_conditions(client, self, desired)
+            .await
+            .map_err(ClusterSyncError::AddonsConditionError)?
+        {
+            debug!("Updated Fleet conditions on the cluster");
+        }
+
+        Ok(())
+    }
+
     /// Adds a dynamic watcher for a specific namespace.
     ///
     /// # Errors
@@ -322,4 +1134,324 @@ impl Cluster {
 
         Ok(Action::await_change())
     }
+
+    /// Adds watches for the control plane and infrastructure cluster kinds referenced
+    /// by the cluster, so changes to these objects re-sync the template values.
+    pub async fn add_reference_dynamic_watches(&self, ctx: Arc<Context>) {
+        let references = [
+            self.control_plane_reference(),
+            self.infrastructure_reference(),
+        ];
+        for reference in references.into_iter().flatten() {
+            let (Some(api_version), Some(kind)) = (reference.api_version, reference.kind) else {
+                continue;
+            };
+            let resource = match reference_resource(&ctx.client, &api_version, &kind).await {
+                Ok(resource) => resource,
+                Err(e) => {
+                    warn!("Unable to discover referenced kind {kind}: {e}");
+                    continue;
+                }
+            };
+            let gvk = GroupVersionKind::gvk(&resource.group, &resource.version, &resource.kind);
+            if !ctx.reference_kinds.write().await.insert(gvk) {
+                continue;
+            }
+
+            // Listed objects may come without type information
+            let types = TypeMeta {
+                api_version: resource.api_version.clone(),
+                kind: resource.kind.clone(),
+            };
+            ctx.references.stream.lock().await.push(
+                metadata_watcher(
+                    Api::<DynamicObject>::all_with(ctx.client.clone(), &resource),
+                    Config::default().any_semantic(),
+                )
+                .map(move |event| event.map(|event| reference_event(event, &types)))
+                .boxed(),
+            );
+
+            info!("Reconciled dynamic watches: added reference watch on {kind}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::fleet_bundle_deployment::{
+        BundleDeployment, BundleDeploymentStatus, BundleDeploymentStatusModifiedStatus,
+        BundleDeploymentStatusNonReadyStatus,
+    };
+
+    use crate::api::{
+        capi_cluster::Cluster,
+        fleet_addon_config::{ClusterConfig, NamingStrategy, TemplateSource},
+    };
+    use kube::api::{DynamicObject, PartialObjectMeta, TypeMeta};
+    use kube::runtime::reflector::ObjectRef;
+    use kube::runtime::watcher;
+    use serde_json::json;
+
+    use super::{
+        BundleSummary, FleetClusterIndex, TEMPLATE_VALUES_RESOLVED_CONDITION, TemplateSourceError,
+        reference_event, select_keys, template_values_condition,
+    };
+
+    fn bundle(status: Option<BundleDeploymentStatus>) -> BundleDeployment {
+        BundleDeployment {
+            status,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bundle_summary() {
+        let bundles = [
+            bundle(None),
+            bundle(Some(BundleDeploymentStatus {
+                ready: Some(true),
+                non_modified: Some(true),
+                ..Default::default()
+            })),
+            bundle(Some(BundleDeploymentStatus {
+                ready: Some(true),
+                non_modified: Some(false),
+                modified_status: Some(vec![BundleDeploymentStatusModifiedStatus::default()]),
+                ..Default::default()
+            })),
+            bundle(Some(BundleDeploymentStatus {
+                ready: Some(false),
+                non_ready_status: Some(vec![BundleDeploymentStatusNonReadyStatus::default()]),
+                ..Default::default()
+            })),
+        ];
+
+        let summary: BundleSummary = bundles.iter().collect();
+        assert_eq!(
+            summary,
+            BundleSummary {
+                desired: 4,
+                ready: 1,
+                modified: 1,
+                non_ready: 2,
+            }
+        );
+        assert_eq!(summary.to_condition()["status"], "False");
+
+        let summary: BundleSummary = bundles[1..2].iter().collect();
+        assert_eq!(summary.to_condition()["status"], "True");
+        assert_eq!(
+            summary.to_condition()["message"],
+            "Bundles ready: 1/1, modified: 0, non-ready: 0"
+        );
+    }
+
+    #[test]
+    fn test_template_values_condition() {
+        let resolved = template_values_condition(None);
+        assert_eq!(resolved["type"], TEMPLATE_VALUES_RESOLVED_CONDITION);
+        assert_eq!(resolved["status"], "True");
+
+        let error = TemplateSourceError::IncompleteReference("ControlPlane".into());
+        assert_eq!(error.key(), Some("ControlPlane"));
+
+        let failed =
+            template_values_condition(Some(("TemplateResolutionFailed", error.to_string())));
+        assert_eq!(failed["status"], "False");
+        assert_eq!(failed["reason"], "TemplateResolutionFailed");
+        assert_eq!(
+            failed["message"],
+            "Template source `ControlPlane` reference is incomplete"
+        );
+    }
+
+    fn cluster(annotations: serde_json::Value) -> Cluster {
+        serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default", "annotations": annotations},
+            "spec": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fleet_cluster_index() {
+        let capi = cluster(json!({}));
+        let config = ClusterConfig {
+            naming: Some(NamingStrategy {
+                prefix: Some("caapf-".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let index = FleetClusterIndex::default();
+        index.insert(&capi.to_cluster(Some(&config)), &capi);
+        assert_eq!(
+            index.get("caapf-test", Some("default")),
+            Some(ObjectRef::from_obj(&capi))
+        );
+        assert_eq!(index.get("test", Some("default")), None);
+        assert_eq!(index.get("caapf-test", Some("other")), None);
+
+        // Renamed Fleet clusters replace the previous name
+        index.insert(&capi.to_cluster(None), &capi);
+        assert_eq!(index.get("caapf-test", Some("default")), None);
+        assert_eq!(
+            index.get("test", Some("default")),
+            Some(ObjectRef::from_obj(&capi))
+        );
+
+        index.remove(&capi);
+        assert_eq!(index.get("test", Some("default")), None);
+    }
+
+    #[test]
+    fn test_reference_event() {
+        let meta: PartialObjectMeta<DynamicObject> = serde_json::from_value(json!({
+            "apiVersion": "meta.k8s.io/v1",
+            "kind": "PartialObjectMetadata",
+            "metadata": {"name": "test-cp", "namespace": "default", "generation": 2},
+        }))
+        .unwrap();
+        let types = TypeMeta {
+            api_version: "controlplane.cluster.x-k8s.io/v1beta2".into(),
+            kind: "KubeadmControlPlane".into(),
+        };
+
+        let watcher::Event::Apply(object) = reference_event(watcher::Event::Apply(meta), &types)
+        else {
+            panic!("expected an apply event");
+        };
+        assert_eq!(object.types, Some(types));
+        assert_eq!(object.metadata.name.as_deref(), Some("test-cp"));
+        assert_eq!(object.metadata.namespace.as_deref(), Some("default"));
+        assert_eq!(object.metadata.generation, Some(2));
+    }
+
+    #[test]
+    fn test_annotation_overrides() {
+        let mut config = ClusterConfig::default();
+        let gpu = cluster(json!({
+            "fleet.addons.cluster.x-k8s.io/agent-namespace": "gpu-agent",
+            "fleet.addons.cluster.x-k8s.io/host-network": "false",
+            "fleet.addons.cluster.x-k8s.io/agent-tolerations":
+                r#"[{"key": "nvidia.com/gpu", "operator": "Exists", "effect": "NoSchedule"}]"#,
+        }));
+
+        gpu.apply_annotation_overrides(&mut config).unwrap();
+        assert!(gpu.import_enabled().unwrap());
+        assert_eq!(config.agent_namespace, Some("gpu-agent".into()));
+        assert_eq!(config.host_network, Some(false));
+
+        let tolerations = config.agent_tolerations.unwrap();
+        assert_eq!(tolerations.len(), 4);
+        assert_eq!(
+            tolerations.last().and_then(|t| t.key.clone()),
+            Some("nvidia.com/gpu".into())
+        );
+
+        let opt_out = cluster(json!({"fleet.addons.cluster.x-k8s.io/import": "false"}));
+        assert!(!opt_out.import_enabled().unwrap());
+
+        let invalid = cluster(json!({"fleet.addons.cluster.x-k8s.io/host-network": "no"}));
+        assert!(
+            invalid
+                .apply_annotation_overrides(&mut ClusterConfig::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_readiness_gates() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {},
+            "status": {
+                "conditions": [
+                    {"type": "InfrastructureReady", "status": "True", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                ],
+                "v1beta2": {
+                    "conditions": [
+                        {"type": "CNIReady", "status": "False", "reason": "Pending", "message": "", "lastTransitionTime": "2025-01-01T00:00:00Z"},
+                    ],
+                },
+            },
+        }))
+        .unwrap();
+
+        let gates = |gates: serde_json::Value| ClusterConfig {
+            readiness_gates: serde_json::from_value(gates).unwrap(),
+            ..Default::default()
+        };
+
+        assert!(cluster.readiness_gates_met(None));
+        assert!(cluster.readiness_gates_met(Some(&ClusterConfig::default())));
+        assert!(cluster.readiness_gates_met(Some(&gates(json!([
+            {"conditionType": "InfrastructureReady"},
+            {"conditionType": "CNIReady", "status": "False"},
+        ])))));
+        assert!(!cluster.readiness_gates_met(Some(&gates(json!([
+            {"conditionType": "CNIReady"},
+        ])))));
+        assert!(!cluster.readiness_gates_met(Some(&gates(json!([
+            {"conditionType": "Missing"},
+        ])))));
+    }
+
+    #[test]
+    fn test_select_keys() {
+        let object = |kind: &str| -> DynamicObject {
+            serde_json::from_value(json!({
+                "apiVersion": "v1",
+                "kind": kind,
+                "metadata": {
+                    "name": "values",
+                    "namespace": "default",
+                    "annotations": {"kubectl.kubernetes.io/last-applied-configuration": "{}"},
+                },
+                "type": "Opaque",
+                "data": {"cidr": "MTAuMC4wLjAvOA==", "password": "c2VjcmV0"},
+            }))
+            .unwrap()
+        };
+        let source = |kind: &str, keys: Option<Vec<String>>| TemplateSource {
+            key: "Values".into(),
+            api_version: "v1".into(),
+            kind: kind.into(),
+            keys,
+            ..Default::default()
+        };
+
+        let mut secret = object("Secret");
+        select_keys(&source("Secret", Some(vec!["cidr".into()])), &mut secret);
+        assert_eq!(
+            serde_json::to_value(&secret).unwrap(),
+            json!({
+                "apiVersion": "v1",
+                "kind": "Secret",
+                "metadata": {"name": "values", "namespace": "default"},
+                "data": {"cidr": "MTAuMC4wLjAvOA=="},
+            })
+        );
+
+        let mut secret = object("Secret");
+        select_keys(&source("Secret", None), &mut secret);
+        assert_eq!(secret.data, json!({"data": {}}));
+
+        let mut config_map = object("ConfigMap");
+        select_keys(&source("ConfigMap", None), &mut config_map);
+        assert_eq!(config_map, object("ConfigMap"));
+
+        select_keys(
+            &source("ConfigMap", Some(vec!["cidr".into()])),
+            &mut config_map,
+        );
+        assert_eq!(config_map.data["data"], json!({"cidr": "MTAuMC4wLjAvOA=="}));
+        assert_eq!(config_map.data["type"], "Opaque");
+    }
 }
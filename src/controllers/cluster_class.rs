@@ -3,18 +3,27 @@ use crate::api::capi_clusterclass::ClusterClass;
 use crate::api::fleet_addon_config::{ClusterClassConfig, FleetAddonConfig};
 use crate::api::fleet_clustergroup::ClusterGroup;
 
+use chrono::{SecondsFormat, Utc};
+use kube::ResourceExt as _;
 use kube::api::PatchParams;
 
 use kube::runtime::controller::Action;
 
+use serde_json::{Value, json};
+use tracing::debug;
+
 use std::sync::Arc;
 
 use super::controller::{
-    Context, FleetBundle, FleetController, fetch_config, get_or_create, patch,
+    Context, FleetBundle, FleetController, GetApi as _, fetch_config, get_or_create, patch,
+    update_conditions,
 };
-use super::{BundleResult, GroupSyncResult};
+use super::{BundleResult, GroupSyncError, GroupSyncResult};
+
+pub static CLUSTER_GROUP_READY_CONDITION: &str = "FleetClusterGroupReady";
 
 pub struct FleetClusterClassBundle {
+    cluster_class: ClusterClass,
     fleet_group: ClusterGroup,
     config: FleetAddonConfig,
 }
@@ -23,20 +32,90 @@ impl FleetBundle for FleetClusterClassBundle {
     #[allow(refining_impl_trait)]
     async fn sync(&mut self, ctx: Arc<Context>) -> GroupSyncResult<Action> {
         if self.config.cluster_class_patch_enabled() {
+            let existing =
+                ClusterGroup::get_api(ctx.client.clone(), self.fleet_group.get_namespace())
+                    .get_opt(&self.fleet_group.name_any())
+                    .await
+                    .map_err(GroupSyncError::StaleLabelsError)?;
             patch(
-                ctx,
+                ctx.clone(),
                 &mut self.fleet_group,
                 &PatchParams::apply("addon-provider-fleet"),
             )
-            .await?
+            .await?;
+            if let Some(existing) = existing {
+                self.fleet_group
+                    .remove_stale_labels(ctx.client.clone(), &existing)
+                    .await?;
+            }
         } else {
-            get_or_create(ctx.clone(), &self.fleet_group).await?
-        };
+            get_or_create(ctx.clone(), &self.fleet_group).await?;
+        }
+
+        self.update_group_condition(ctx).await?;
 
         Ok(Action::await_change())
     }
 }
 
+impl FleetClusterClassBundle {
+    /// Mirrors the `ClusterGroup` status onto the `ClusterClass` as the
+    /// `FleetClusterGroupReady` condition, once Fleet reported the group status.
+    async fn update_group_condition(&self, ctx: Arc<Context>) -> GroupSyncResult<()> {
+        let status = ClusterGroup::get_api(ctx.client.clone(), self.fleet_group.get_namespace())
+            .get_opt(&self.fleet_group.name_any())
+            .await
+            .map_err(GroupSyncError::StatusLookup)?
+            .and_then(|group| group.status);
+        let Some(status) = status else {
+            return Ok(());
+        };
+
+        let condition = group_condition(&serde_json::to_value(status)?);
+        if update_conditions(ctx.client.clone(), &self.cluster_class, vec![condition])
+            .await
+            .map_err(GroupSyncError::ConditionError)?
+        {
+            debug!("Updated ClusterGroup condition on the cluster class");
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the `ClusterGroup` status as a CAPI `v1beta1` condition, with the number of
+/// clusters, non-ready clusters and ready bundles in the group.
+fn group_condition(status: &Value) -> Value {
+    let count = |pointer| status.pointer(pointer).and_then(Value::as_i64).unwrap_or(0);
+    let clusters = count("/clusterCount");
+    let non_ready = count("/nonReadyClusterCount");
+    let ready = count("/summary/ready");
+    let desired = count("/summary/desiredReady");
+
+    let message =
+        format!("Clusters: {clusters}, non-ready: {non_ready}, bundles ready: {ready}/{desired}");
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    if non_ready == 0 && ready >= desired {
+        json!({
+            "type": CLUSTER_GROUP_READY_CONDITION,
+            "status": "True",
+            "reason": "ClustersReady",
+            "message": message,
+            "lastTransitionTime": now,
+        })
+    } else {
+        json!({
+            "type": CLUSTER_GROUP_READY_CONDITION,
+            "status": "False",
+            "severity": "Warning",
+            "reason": "ClustersNotReady",
+            "message": message,
+            "lastTransitionTime": now,
+        })
+    }
+}
+
 impl FleetController for ClusterClass {
     type Bundle = FleetClusterClassBundle;
 
@@ -47,6 +126,7 @@ impl FleetController for ClusterClass {
         }
 
         let mut fleet_group: ClusterGroup = self.into();
+        fleet_group.propagate_labels(self, config.cluster_class_label_filter());
         if let Some(ClusterClassConfig {
             set_owner_references: Some(true),
             ..
@@ -57,8 +137,49 @@ impl FleetController for ClusterClass {
         }
 
         Ok(Some(FleetClusterClassBundle {
+            cluster_class: self.clone(),
             fleet_group,
             config,
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{CLUSTER_GROUP_READY_CONDITION, group_condition};
+
+    #[test]
+    fn test_group_condition() {
+        let ready = group_condition(&json!({
+            "clusterCount": 3,
+            "nonReadyClusterCount": 0,
+            "summary": {"ready": 2, "desiredReady": 2},
+        }));
+        assert_eq!(ready["type"], CLUSTER_GROUP_READY_CONDITION);
+        assert_eq!(ready["status"], "True");
+        assert_eq!(ready["reason"], "ClustersReady");
+        assert_eq!(
+            ready["message"],
+            "Clusters: 3, non-ready: 0, bundles ready: 2/2"
+        );
+        assert!(ready.get("severity").is_none());
+
+        let not_ready = group_condition(&json!({
+            "clusterCount": 3,
+            "nonReadyClusterCount": 1,
+            "summary": {"ready": 1, "desiredReady": 2},
+        }));
+        assert_eq!(not_ready["status"], "False");
+        assert_eq!(not_ready["severity"], "Warning");
+        assert_eq!(not_ready["reason"], "ClustersNotReady");
+
+        let empty = group_condition(&json!({}));
+        assert_eq!(empty["status"], "True");
+        assert_eq!(
+            empty["message"],
+            "Clusters: 0, non-ready: 0, bundles ready: 0/0"
+        );
+    }
+}
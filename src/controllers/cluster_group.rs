@@ -4,14 +4,16 @@ use crate::controllers::controller::GetApi;
 
 use kube::ResourceExt;
 use kube::api::{Patch, PatchParams};
+use kube::client::Client;
 use kube::runtime::controller::Action;
-use serde_json::json;
+use serde_json::{Value, json};
+use tracing::debug;
 
 use std::ops::Deref;
 use std::sync::Arc;
 
-use super::controller::{Context, FLEET_FINALIZER, patch};
-use super::{GroupSyncResult, SyncError};
+use super::controller::{Context, FLEET_FINALIZER, fetch_config, patch};
+use super::{GroupSyncError, GroupSyncResult, SyncError};
 
 impl ClusterGroup {
     /// Reconciles the `ClusterGroup` resource.
@@ -27,18 +29,18 @@ impl ClusterGroup {
     async fn sync(&mut self, ctx: Arc<Context>) -> GroupSyncResult<Action> {
         if let Some(cc_ref) = self.cluster_class_ref() {
             let class = ctx.client.fetch::<ClusterClass>(&cc_ref).await?;
-            self.labels_mut().extend(
-                class
-                    .labels()
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_string())),
-            );
+            let config = fetch_config(ctx.client.clone()).await?;
+
+            let existing = self.clone();
+            self.propagate_labels(&class, config.cluster_class_label_filter());
             patch(
                 ctx.clone(),
                 self,
                 &PatchParams::apply("addon-provider-fleet"),
             )
             .await?;
+            self.remove_stale_labels(ctx.client.clone(), &existing)
+                .await?;
         }
 
         if self.finalizers().iter().any(|f| f == FLEET_FINALIZER) {
@@ -54,4 +56,34 @@ impl ClusterGroup {
 
         Ok(Action::await_change())
     }
+
+    /// Removes labels previously propagated to the existing group, which are no longer
+    /// propagated from the `ClusterClass`.
+    pub(crate) async fn remove_stale_labels(
+        &self,
+        client: Client,
+        existing: &Self,
+    ) -> GroupSyncResult<()> {
+        let labels = self.stale_labels(existing);
+        if labels.is_empty() {
+            return Ok(());
+        }
+
+        let removed: serde_json::Map<String, Value> = labels
+            .iter()
+            .map(|key| (key.clone(), Value::Null))
+            .collect();
+        Self::get_api(client, self.get_namespace())
+            .patch_metadata(
+                &self.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(json!({"metadata": {"labels": removed}})),
+            )
+            .await
+            .map_err(GroupSyncError::StaleLabelsError)?;
+
+        debug!("Removed stale labels {labels:?} from ClusterGroup");
+
+        Ok(())
+    }
 }
@@ -1,6 +1,9 @@
+use crate::api::capi_cluster::ClusterApiVersion;
 use crate::api::comparable::ResourceDiff;
 use crate::api::fleet_addon_config::FleetAddonConfig;
+use crate::api::fleet_addon_config_override::{FleetAddonConfigOverride, OVERRIDE_NAME};
 use crate::controllers::PatchError;
+use crate::controllers::cluster::FleetClusterIndex;
 use crate::metrics::Diagnostics;
 use crate::multi_dispatcher::{BroadcastStream, MultiDispatcher, typed_gvk};
 use crate::{Error, Metrics, telemetry};
@@ -10,17 +13,19 @@ use futures::Stream;
 use futures::stream::SelectAll;
 use k8s_openapi::{ClusterResourceScope, NamespaceResourceScope};
 
-use kube::api::{DynamicObject, Patch, PatchParams, PostParams};
+use kube::api::{ApiResource, DynamicObject, GroupVersionKind, Patch, PatchParams, PostParams};
 
 use kube::runtime::events::{Event, EventType};
 use kube::runtime::{finalizer, watcher};
 
-use kube::{api::Api, client::Client, runtime::controller::Action};
+use kube::{ResourceExt as _, api::Api, client::Client, runtime::controller::Action};
 
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
 use tracing::field::display;
 
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -50,6 +55,12 @@ pub struct Context {
     pub dispatcher: MultiDispatcher,
     // shared stream of dynamic events
     pub stream: BroadcastStream<DynamicStream>,
+    // shared stream of objects referenced by clusters
+    pub references: BroadcastStream<DynamicStream>,
+    // kinds of referenced objects with an active watch
+    pub reference_kinds: Arc<RwLock<HashSet<GroupVersionKind>>>,
+    // imported Fleet clusters by the CAPI cluster they are created from
+    pub fleet_clusters: FleetClusterIndex,
     // k8s minor version
     pub version: u32,
     // Controller readiness barrier
@@ -172,6 +183,79 @@ where
     Ok(Action::await_change())
 }
 
+/// Merges the desired conditions into the status of a CAPI object, and returns whether
+/// the status was patched. Conditions follow the format of the served CAPI version, and
+/// the last transition time is kept while the condition status is unchanged.
+pub(crate) async fn update_conditions<R>(
+    client: Client,
+    res: &R,
+    desired: Vec<Value>,
+) -> kube::Result<bool>
+where
+    R: kube::Resource<DynamicType = (), Scope = NamespaceResourceScope>,
+{
+    let version = ClusterApiVersion::served();
+    let api: Api<DynamicObject> = Api::namespaced_with(
+        client,
+        &res.namespace().unwrap_or_default(),
+        &ApiResource::erase::<R>(&()),
+    );
+    let object = api.get_status(&res.name_any()).await?;
+
+    // Conditions owned by the served version are always stored in `status.conditions`.
+    let mut conditions = object
+        .data
+        .pointer("/status/conditions")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut changed = false;
+    for desired in desired {
+        let mut desired = version.to_condition(desired);
+        match conditions.iter_mut().find(|c| c["type"] == desired["type"]) {
+            Some(existing)
+                if existing["status"] == desired["status"]
+                    && existing["reason"] == desired["reason"]
+                    && existing["message"] == desired["message"] => {}
+            Some(existing) => {
+                if existing["status"] == desired["status"] {
+                    desired["lastTransitionTime"] = existing["lastTransitionTime"].clone();
+                }
+                *existing = desired;
+                changed = true;
+            }
+            None => {
+                conditions.push(desired);
+                changed = true;
+            }
+        }
+    }
+
+    if !changed {
+        return Ok(false);
+    }
+
+    // Conditions are an atomic list, so the resource version guards against
+    // overwriting conditions set concurrently by the CAPI controllers.
+    let patch = json!({
+        "metadata": {
+            "resourceVersion": object.resource_version(),
+        },
+        "status": {
+            "conditions": conditions,
+        }
+    });
+    api.patch_status(
+        &res.name_any(),
+        &PatchParams::default(),
+        &Patch::Merge(&patch),
+    )
+    .await?;
+
+    Ok(true)
+}
+
 /// Helper trait for getting [`kube::Api`] instances for a Kubernetes resource's scope
 ///
 /// Not intended to be implemented manually, it is blanket-implemented for all types that implement [`Resource`]
@@ -267,6 +351,27 @@ pub(crate) async fn fetch_config(client: Client) -> ConfigFetchResult<FleetAddon
         .unwrap_or_default())
 }
 
+/// Fetches the global config with the namespace `FleetAddonConfigOverride` applied over it.
+///
+/// Precedence: namespace override, global `FleetAddonConfig`, built-in defaults.
+pub(crate) async fn fetch_namespace_config(
+    client: Client,
+    namespace: &str,
+) -> ConfigFetchResult<FleetAddonConfig> {
+    let mut config = fetch_config(client.clone()).await?;
+
+    let config_override: Option<FleetAddonConfigOverride> = Api::namespaced(client, namespace)
+        .get_opt(OVERRIDE_NAME)
+        .await?;
+
+    if let (Some(cluster), Some(config_override)) = (config.spec.cluster.as_mut(), config_override)
+    {
+        config_override.spec.cluster.apply(cluster);
+    }
+
+    Ok(config)
+}
+
 pub(crate) trait FleetBundle {
     async fn sync(&mut self, ctx: Arc<Context>) -> Result<Action, impl Into<SyncError>>;
     #[allow(clippy::unused_async)]
@@ -293,6 +398,17 @@ where
         let api = Self::get_api(ctx.client.clone(), self.get_namespace());
         debug!("Reconciling");
 
+        if !self.finalized() && self.meta().deletion_timestamp.is_none() {
+            return match self.to_bundle(ctx.clone()).await? {
+                Some(mut bundle) => bundle
+                    .sync(ctx)
+                    .await
+                    .map_err(Into::into)
+                    .map_err(Into::into),
+                _ => Ok(Action::await_change()),
+            };
+        }
+
         finalizer(&api, FLEET_FINALIZER, self, |event| async {
             match event {
                 finalizer::Event::Apply(c) => match c.to_bundle(ctx.clone()).await? {
@@ -342,4 +458,10 @@ where
     }
 
     async fn to_bundle(&self, ctx: Arc<Context>) -> BundleResult<Option<Self::Bundle>>;
+
+    /// Objects are reconciled with a finalizer, unless they opted out of the management.
+    /// Objects being removed keep the finalizer until the cleanup is done.
+    fn finalized(&self) -> bool {
+        true
+    }
 }
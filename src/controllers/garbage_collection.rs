@@ -0,0 +1,465 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use fleet_api_rs::fleet_bundle_namespace_mapping::BundleNamespaceMappingBundleSelector;
+use kube::api::{DeleteParams, ListParams, Patch, PatchParams};
+use kube::runtime::controller::Action;
+use kube::runtime::events::{Event, EventType};
+use kube::{Api, Resource, ResourceExt as _};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use tracing::info;
+
+use crate::api::bundle_namespace_mapping::{BundleNamespaceMapping, NAMESPACE_NAME_LABEL};
+use crate::api::capi_cluster::{CLUSTER_API_GROUP, Cluster};
+use crate::api::capi_clusterclass::ClusterClass;
+use crate::api::fleet_addon_config::FleetAddonConfig;
+use crate::api::fleet_clustergroup::{CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup};
+
+use super::controller::{Context, GetApi};
+use super::{GarbageCollectionError, GarbageCollectionResult};
+
+/// `ClusterClasses` and class references of CAPI clusters, which keep class objects in use.
+#[derive(Default)]
+struct Sources {
+    /// Namespace and name of every `ClusterClass`.
+    classes: HashSet<(String, String)>,
+    /// Cluster namespace, class namespace and class name of every cluster topology.
+    references: HashSet<(String, String, String)>,
+}
+
+impl Sources {
+    async fn fetch(ctx: &Context) -> GarbageCollectionResult<Self> {
+        let classes = Api::<ClusterClass>::all(ctx.client.clone())
+            .list_metadata(&ListParams::default())
+            .await
+            .map_err(GarbageCollectionError::Lookup)?
+            .into_iter()
+            .map(|class| (class.namespace().unwrap_or_default(), class.name_any()))
+            .collect();
+
+        let references = Api::<Cluster>::all(ctx.client.clone())
+            .list(&ListParams::default())
+            .await
+            .map_err(GarbageCollectionError::Lookup)?
+            .into_iter()
+            .filter_map(|cluster| {
+                let namespace = cluster.namespace()?;
+                let class = cluster.cluster_class_name()?.to_string();
+                let class_namespace = cluster
+                    .cluster_class_namespace()
+                    .map_or_else(|| namespace.clone(), String::from);
+                Some((namespace, class_namespace, class))
+            })
+            .collect();
+
+        Ok(Self {
+            classes,
+            references,
+        })
+    }
+
+    /// Returns the reason for removing the `ClusterGroup`, if it is orphaned.
+    /// Groups in the cluster namespace are named `{class}.{class_namespace}`, and are orphaned
+    /// once no cluster in the namespace references the class.
+    fn orphaned_group(&self, group: &ClusterGroup) -> Option<String> {
+        let class = group.cluster_class_name()?;
+        let class_namespace = group.cluster_class_namespace()?;
+        if !self
+            .classes
+            .contains(&(class_namespace.clone(), class.clone()))
+        {
+            return Some(format!(
+                "ClusterClass {class_namespace}/{class} no longer exists"
+            ));
+        }
+
+        let namespace = group.namespace().unwrap_or_default();
+        let referenced =
+            self.references
+                .contains(&(namespace.clone(), class_namespace.clone(), class.clone()));
+        (group.name_any() == format!("{class}.{class_namespace}") && !referenced).then(|| {
+            format!("No cluster in {namespace} references ClusterClass {class_namespace}/{class}")
+        })
+    }
+
+    /// Returns the reason for removing the `BundleNamespaceMapping`, if it is orphaned.
+    /// Mappings are named after the cluster namespace, and created in the class namespace.
+    fn orphaned_mapping(&self, mapping: &BundleNamespaceMapping) -> Option<String> {
+        if !managed_mapping(mapping) {
+            return None;
+        }
+
+        let cluster_namespace = mapping.name_any();
+        let class_namespace = mapping.namespace().unwrap_or_default();
+        (!self.references_mapping(mapping)).then(|| {
+            format!(
+                "No cluster in {cluster_namespace} references a ClusterClass in {class_namespace}"
+            )
+        })
+    }
+
+    /// Checks if a cluster in the namespace the mapping is named after references
+    /// a `ClusterClass` in the mapping namespace.
+    fn references_mapping(&self, mapping: &BundleNamespaceMapping) -> bool {
+        let cluster_namespace = mapping.name_any();
+        let class_namespace = mapping.namespace().unwrap_or_default();
+        self.references
+            .iter()
+            .any(|(namespace, reference_namespace, _)| {
+                *namespace == cluster_namespace && *reference_namespace == class_namespace
+            })
+    }
+
+    /// Mappings created before the `ClusterClass` namespace label was set are recognized while
+    /// a cluster still references the class namespace, and they select all bundles for the
+    /// namespace they are named after, like the mappings created for the cluster.
+    fn legacy_mapping(&self, mapping: &BundleNamespaceMapping) -> bool {
+        let selector = &mapping.namespace_selector;
+        let selects_name = selector.match_expressions.is_none()
+            && selector.match_labels.as_ref().is_some_and(|labels| {
+                labels.len() == 1 && labels.get(NAMESPACE_NAME_LABEL) == Some(&mapping.name_any())
+            });
+
+        !managed_mapping(mapping)
+            && selects_name
+            && mapping.bundle_selector == BundleNamespaceMappingBundleSelector::default()
+            && self.references_mapping(mapping)
+    }
+}
+
+/// Mappings are managed by the controller if they have the `ClusterClass` namespace label,
+/// or are owned by a CAPI cluster.
+fn managed_mapping(mapping: &BundleNamespaceMapping) -> bool {
+    mapping.labels().contains_key(CLUSTER_CLASS_NAMESPACE_LABEL)
+        || mapping.owner_references().iter().any(|owner| {
+            owner.kind == "Cluster"
+                && owner
+                    .api_version
+                    .split_once('/')
+                    .is_some_and(|(group, _)| group == CLUSTER_API_GROUP)
+        })
+}
+
+/// Set once the legacy mappings are labeled, the migration runs once per controller start.
+static MAPPINGS_MIGRATED: AtomicBool = AtomicBool::new(false);
+
+/// Adds the `ClusterClass` namespace label to mappings created by previous controller versions,
+/// so they are removed once no longer referenced. Unrecognized mappings are never removed.
+async fn migrate_mappings(
+    ctx: &Context,
+    sources: &Sources,
+    mappings: &mut [BundleNamespaceMapping],
+) -> GarbageCollectionResult<()> {
+    if MAPPINGS_MIGRATED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    for mapping in mappings.iter_mut().filter(|m| sources.legacy_mapping(m)) {
+        let class_namespace = mapping.namespace().unwrap_or_default();
+        let patch = json!({
+            "metadata": {
+                "labels": {
+                    CLUSTER_CLASS_NAMESPACE_LABEL: class_namespace,
+                }
+            }
+        });
+        BundleNamespaceMapping::get_api(ctx.client.clone(), mapping.get_namespace())
+            .patch_metadata(
+                &mapping.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .map_err(GarbageCollectionError::Migration)?;
+        mapping
+            .labels_mut()
+            .insert(CLUSTER_CLASS_NAMESPACE_LABEL.to_string(), class_namespace);
+        info!(
+            "Labeled legacy BundleNamespaceMapping {}/{}",
+            mapping.get_namespace(),
+            mapping.name_any()
+        );
+    }
+
+    MAPPINGS_MIGRATED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+impl FleetAddonConfig {
+    /// Periodically removes `ClusterGroups` with `ClusterClass` labels and
+    /// `BundleNamespaceMappings` created for class references, whose class or referencing
+    /// clusters no longer exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if objects can't be listed, removed or reported.
+    pub async fn reconcile_garbage_collection(
+        self: Arc<Self>,
+        ctx: Arc<Context>,
+    ) -> crate::Result<Action> {
+        let Some(gc) = self
+            .spec
+            .cluster_class
+            .as_ref()
+            .and_then(|config| config.garbage_collection.as_ref())
+        else {
+            return Ok(Action::await_change());
+        };
+
+        let sources = Sources::fetch(&ctx).await?;
+
+        let groups = Api::<ClusterGroup>::all(ctx.client.clone())
+            .list(&ListParams::default().labels_from(&ClusterGroup::group_selector()))
+            .await
+            .map_err(GarbageCollectionError::Lookup)?;
+        for group in groups {
+            if let Some(reason) = sources.orphaned_group(&group) {
+                collect(&ctx, &group, &reason, gc.dry_run()).await?;
+            }
+        }
+
+        let mut mappings = Api::<BundleNamespaceMapping>::all(ctx.client.clone())
+            .list(&ListParams::default())
+            .await
+            .map_err(GarbageCollectionError::Lookup)?
+            .items;
+        migrate_mappings(&ctx, &sources, &mut mappings).await?;
+        for mapping in mappings {
+            if let Some(reason) = sources.orphaned_mapping(&mapping) {
+                collect(&ctx, &mapping, &reason, gc.dry_run()).await?;
+            }
+        }
+
+        Ok(Action::requeue(gc.interval()))
+    }
+}
+
+/// Publishes an event for the orphaned object, and removes it unless in dry-run mode.
+async fn collect<R>(
+    ctx: &Context,
+    object: &R,
+    reason: &str,
+    dry_run: bool,
+) -> GarbageCollectionResult<()>
+where
+    R: Resource<DynamicType = ()> + GetApi + Clone + DeserializeOwned + Debug,
+{
+    let kind = R::kind(&());
+    let name = object.name_any();
+    let (event_reason, note) = if dry_run {
+        (
+            "OrphanDetected",
+            format!("Orphaned {kind} {name} would be removed: {reason}"),
+        )
+    } else {
+        (
+            "OrphanRemoved",
+            format!("Removing orphaned {kind} {name}: {reason}"),
+        )
+    };
+    info!("{note}");
+
+    match ctx
+        .diagnostics
+        .read()
+        .await
+        .recorder(ctx.client.clone())
+        .publish(
+            &Event {
+                type_: EventType::Normal,
+                reason: event_reason.into(),
+                note: Some(note),
+                action: "GarbageCollection".into(),
+                secondary: None,
+            },
+            &object.object_ref(&()),
+        )
+        .await
+    {
+        // Ignore forbidden errors on event publishing
+        Err(kube::Error::Api(e)) if &e.reason == "Forbidden" => (),
+        e => e.map_err(GarbageCollectionError::Event)?,
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    match R::get_api(ctx.client.clone(), object.get_namespace())
+        .delete(&name, &DeleteParams::default())
+        .await
+    {
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        e => e.map(|_| ()).map_err(GarbageCollectionError::Delete),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fleet_api_rs::fleet_bundle_namespace_mapping::BundleNamespaceMappingNamespaceSelector;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::api::ObjectMeta;
+
+    use crate::api::bundle_namespace_mapping::{BundleNamespaceMapping, NAMESPACE_NAME_LABEL};
+    use crate::api::fleet_clustergroup::{
+        CLUSTER_CLASS_LABEL, CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup,
+    };
+
+    use super::Sources;
+
+    fn group(name: &str, namespace: &str, class: &str, class_namespace: &str) -> ClusterGroup {
+        ClusterGroup {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                namespace: Some(namespace.into()),
+                labels: Some(
+                    [
+                        (CLUSTER_CLASS_LABEL.to_string(), class.to_string()),
+                        (
+                            CLUSTER_CLASS_NAMESPACE_LABEL.to_string(),
+                            class_namespace.to_string(),
+                        ),
+                    ]
+                    .into(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn mapping(name: &str, namespace: &str) -> BundleNamespaceMapping {
+        BundleNamespaceMapping {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                namespace: Some(namespace.into()),
+                labels: Some(
+                    [(
+                        CLUSTER_CLASS_NAMESPACE_LABEL.to_string(),
+                        namespace.to_string(),
+                    )]
+                    .into(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn unlabeled_mapping(name: &str, namespace: &str, selected: &str) -> BundleNamespaceMapping {
+        BundleNamespaceMapping {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                namespace: Some(namespace.into()),
+                ..Default::default()
+            },
+            namespace_selector: BundleNamespaceMappingNamespaceSelector {
+                match_labels: Some(
+                    [(NAMESPACE_NAME_LABEL.to_string(), selected.to_string())].into(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_orphaned_objects() {
+        let sources = Sources {
+            classes: [("classes".to_string(), "quick-start".to_string())].into(),
+            references: [(
+                "team-a".to_string(),
+                "classes".to_string(),
+                "quick-start".to_string(),
+            )]
+            .into(),
+        };
+
+        // Class group in the class namespace
+        assert!(
+            sources
+                .orphaned_group(&group("quick-start", "classes", "quick-start", "classes"))
+                .is_none()
+        );
+        assert!(
+            sources
+                .orphaned_group(&group("removed", "classes", "removed", "classes"))
+                .is_some()
+        );
+
+        // Class groups in the cluster namespace
+        assert!(
+            sources
+                .orphaned_group(&group(
+                    "quick-start.classes",
+                    "team-a",
+                    "quick-start",
+                    "classes"
+                ))
+                .is_none()
+        );
+        assert!(
+            sources
+                .orphaned_group(&group(
+                    "quick-start.classes",
+                    "team-b",
+                    "quick-start",
+                    "classes"
+                ))
+                .is_some()
+        );
+
+        assert!(
+            sources
+                .orphaned_mapping(&mapping("team-a", "classes"))
+                .is_none()
+        );
+        assert!(
+            sources
+                .orphaned_mapping(&mapping("team-b", "classes"))
+                .is_some()
+        );
+        assert!(
+            sources
+                .orphaned_mapping(&mapping("team-a", "other"))
+                .is_some()
+        );
+
+        // Mappings without the class namespace label are only removed if owned by a cluster
+        assert!(
+            sources
+                .orphaned_mapping(&unlabeled_mapping("team-b", "classes", "team-b"))
+                .is_none()
+        );
+        let mut owned = unlabeled_mapping("team-b", "classes", "team-b");
+        owned.metadata.owner_references = Some(vec![OwnerReference {
+            api_version: "cluster.x-k8s.io/v1beta1".into(),
+            kind: "Cluster".into(),
+            name: "cluster".into(),
+            ..Default::default()
+        }]);
+        assert!(sources.orphaned_mapping(&owned).is_some());
+    }
+
+    #[test]
+    fn test_legacy_mappings() {
+        let sources = Sources {
+            classes: [("classes".to_string(), "quick-start".to_string())].into(),
+            references: [(
+                "team-a".to_string(),
+                "classes".to_string(),
+                "quick-start".to_string(),
+            )]
+            .into(),
+        };
+
+        assert!(sources.legacy_mapping(&unlabeled_mapping("team-a", "classes", "team-a")));
+        assert!(!sources.legacy_mapping(&mapping("team-a", "classes")));
+        assert!(!sources.legacy_mapping(&unlabeled_mapping("team-b", "classes", "team-b")));
+        assert!(!sources.legacy_mapping(&unlabeled_mapping("team-a", "classes", "team-c")));
+        assert!(!sources.legacy_mapping(&unlabeled_mapping("team-a", "other", "team-a")));
+    }
+}
@@ -1,24 +1,31 @@
+use std::fmt::Display;
+
+use semver::{Version, VersionReq};
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{Value, json};
+use sha2::{Digest as _, Sha256};
 use tracing::debug;
 
 use crate::{
-    api::fleet_addon_config::{FeatureGates, Install},
-    controllers::helm::{
-        FleetCRDInstallError, FleetInstallError, MetadataGetError, RepoAddError, RepoAddResult,
-        RepoSearchError,
+    api::fleet_addon_config::{
+        EXPERIMENTAL_HELM_OPS, EXPERIMENTAL_OCI_STORAGE, FLEET_REPOSITORY, FeatureGates,
+        FleetInstall, Install,
     },
+    controllers::helm::{FleetCRDInstallError, FleetInstallError, RepoAddError, RepoAddResult},
 };
-use helm_r2g::{
-    AddRequest, HelmCall, HelmCallImpl, InstallRequest, ListRequest, SearchRequest, UpgradeRequest,
-};
+use helm_r2g::{AddRequest, HelmCall, HelmCallImpl, InstallRequest, UpgradeRequest};
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client, api::ListParams};
 
-use super::{FleetCRDInstallResult, FleetInstallResult, MetadataGetResult, RepoSearchResult};
+use super::{
+    FleetCRDInstallResult, FleetInstallResult, MetadataGetResult, RepoSearchResult, storage,
+};
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Default, Clone)]
 pub struct FleetChart {
-    pub repo: String,
+    pub source: ChartSource,
+    pub auth: ChartAuth,
     pub version: Option<Install>,
     pub namespace: String,
 
@@ -29,6 +36,149 @@ pub struct FleetChart {
     pub bootstrap_local_cluster: bool,
 
     pub feature_gates: FeatureGates,
+
+    /// User provided chart values.
+    pub values: Value,
+
+    pub allow_prerelease: bool,
+    pub allow_downgrade: bool,
+}
+
+/// Location of the `fleet` and `fleet-crd` charts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChartSource {
+    /// Helm repository URL, added under the `fleet` name.
+    Repository(String),
+
+    /// OCI reference, which charts are pulled from directly.
+    Oci(String),
+}
+
+impl Default for ChartSource {
+    fn default() -> Self {
+        Self::Repository(FLEET_REPOSITORY.into())
+    }
+}
+
+impl From<Option<&FleetInstall>> for ChartSource {
+    fn from(install: Option<&FleetInstall>) -> Self {
+        match install {
+            Some(FleetInstall {
+                oci_repository: Some(reference),
+                ..
+            }) => Self::Oci(reference.trim_end_matches('/').into()),
+            Some(FleetInstall {
+                repository: Some(repository),
+                ..
+            }) => Self::Repository(repository.clone()),
+            _ => Self::default(),
+        }
+    }
+}
+
+impl Display for ChartSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Repository(url) | Self::Oci(url) => f.write_str(url),
+        }
+    }
+}
+
+impl ChartSource {
+    /// Returns the chart reference to install or upgrade from.
+    #[must_use]
+    pub fn chart(&self, name: &str) -> String {
+        match self {
+            Self::Repository(_) => format!("fleet/{name}"),
+            Self::Oci(reference) => format!("{reference}/{name}"),
+        }
+    }
+}
+
+/// Action to take for a chart, resolved from the installed and available versions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolution {
+    /// Install the chart version, or the latest one if empty.
+    Install(String),
+
+    /// Upgrade to the chart version.
+    Upgrade(String),
+
+    /// The installed version is up to date.
+    Keep,
+
+    /// The selected version is older than the installed one, and downgrades are not allowed.
+    DowngradeBlocked { installed: String, selected: String },
+
+    /// No available version satisfies the requirement.
+    Unavailable,
+}
+
+/// Parses the chart app version, allowing a leading `v`.
+fn parse_version(version: &str) -> Option<Version> {
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// Resolves a pinned version, which can't be parsed, by comparing the app versions as strings.
+/// Without ordering, any other installed version is upgraded to the pinned one.
+fn resolve_exact(
+    version: &str,
+    installed: Option<&ChartInfo>,
+    available: &[&ChartSearch],
+) -> Resolution {
+    let matches =
+        |app_version: &str| app_version.strip_prefix('v').unwrap_or(app_version) == version;
+    let selected = available
+        .iter()
+        .find(|r| matches(&r.chart.metadata.app_version));
+
+    match (installed, selected) {
+        (None, Some(search)) => Resolution::Install(search.chart.metadata.chart_version()),
+        (None, None) => Resolution::Unavailable,
+        (Some(_), None) => Resolution::Keep,
+        (Some(installed), Some(_)) if matches(&installed.chart.metadata.app_version) => {
+            Resolution::Keep
+        }
+        (Some(_), Some(search)) => Resolution::Upgrade(search.chart.metadata.chart_version()),
+    }
+}
+
+/// Credentials and CA bundle used to access the chart source.
+#[derive(Default, Clone)]
+pub struct ChartAuth {
+    pub username: String,
+    pub password: String,
+    pub ca_file: String,
+}
+
+/// Returns the top level keys, which differ between the installed and the desired values.
+#[must_use]
+pub fn values_drift(installed: &Value, desired: &Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let installed = installed.as_object().unwrap_or(&empty);
+    let desired = desired.as_object().unwrap_or(&empty);
+    let mut keys: Vec<String> = installed
+        .keys()
+        .chain(desired.keys())
+        .filter(|key| installed.get(*key) != desired.get(*key))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Deep merges the overlay into the values. Maps are merged recursively,
+/// while other values, including lists, are replaced.
+pub fn merge_values(values: &mut Value, overlay: Value) {
+    match (values, overlay) {
+        (Value::Object(values), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_values(values.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (values, overlay) => *values = overlay,
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -38,6 +188,9 @@ pub struct ChartInfo {
     #[serde(default)]
     pub chart: Chart,
     pub info: Info,
+    /// User supplied values of the release.
+    #[serde(default)]
+    pub config: Value,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -53,6 +206,19 @@ pub struct Metadata {
     #[serde(default)]
     pub app_version: String,
     pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+impl Metadata {
+    /// Returns the chart version, which falls back to the app version if not set.
+    #[must_use]
+    pub fn chart_version(&self) -> String {
+        match self.version.as_str() {
+            "" => self.app_version.clone(),
+            version => version.into(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -77,15 +243,137 @@ pub struct EmbeddedChart {
 }
 
 impl FleetChart {
+    /// Returns the values for the fleet chart. User values are overridden by the
+    /// bootstrap and feature gate settings, while other `extraEnv` entries are kept.
+    #[must_use]
+    pub fn values(&self) -> Value {
+        let managed = [
+            (
+                EXPERIMENTAL_OCI_STORAGE,
+                self.feature_gates.experimental_oci_storage,
+            ),
+            (
+                EXPERIMENTAL_HELM_OPS,
+                self.feature_gates.experimental_helm_ops,
+            ),
+        ];
+
+        let mut values = match &self.values {
+            Value::Object(_) => self.values.clone(),
+            _ => json!({}),
+        };
+        let mut extra_env: Vec<Value> = values["extraEnv"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|env| !managed.iter().any(|(name, _)| env["name"] == *name))
+            .cloned()
+            .collect();
+        extra_env.extend(
+            managed.map(|(name, enabled)| json!({"name": name, "value": enabled.to_string()})),
+        );
+
+        merge_values(
+            &mut values,
+            json!({
+                "bootstrap": {
+                    "enabled": self.bootstrap_local_cluster.to_string(),
+                },
+                "extraEnv": extra_env,
+            }),
+        );
+        values
+    }
+
+    /// Selects the version of the chart to install or upgrade to, as the highest available
+    /// version satisfying the install requirement.
+    #[must_use]
+    pub fn resolve(
+        &self,
+        chart: &str,
+        installed: Option<&ChartInfo>,
+        available: &[ChartSearch],
+    ) -> Resolution {
+        let install = self.version.clone().unwrap_or_default();
+        let name = format!("fleet/{chart}");
+        let available: Vec<&ChartSearch> = available.iter().filter(|r| r.name == name).collect();
+        let Ok(requirement) = install.requirement() else {
+            // Pinned versions, which are not valid semver, are matched as exact strings
+            return match install.normalized() {
+                Install::Version(version) => resolve_exact(&version, installed, &available),
+                _ => Resolution::Unavailable,
+            };
+        };
+        let selected = available
+            .iter()
+            .filter_map(|r| Some((parse_version(&r.chart.metadata.app_version)?, *r)))
+            .filter(|(version, _)| self.matches(&requirement, version))
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+
+        match (installed, selected) {
+            (None, Some((_, search))) => Resolution::Install(search.chart.metadata.chart_version()),
+            // Charts in OCI registries are listed without a version, the latest one is installed
+            (None, None)
+                if available
+                    .iter()
+                    .any(|r| r.chart.metadata.app_version.is_empty()) =>
+            {
+                Resolution::Install(String::new())
+            }
+            (None, None) => Resolution::Unavailable,
+            (Some(_), None) => Resolution::Keep,
+            (Some(_), Some(_)) if !install.upgrades() => Resolution::Keep,
+            (Some(installed), Some((selected, search))) => {
+                match parse_version(&installed.chart.metadata.app_version) {
+                    Some(current) if current == selected => Resolution::Keep,
+                    Some(current) if current > selected && !self.allow_downgrade => {
+                        Resolution::DowngradeBlocked {
+                            installed: current.to_string(),
+                            selected: selected.to_string(),
+                        }
+                    }
+                    _ => Resolution::Upgrade(search.chart.metadata.chart_version()),
+                }
+            }
+        }
+    }
+
+    /// Checks the version against the requirement. Pre-releases are matched by their
+    /// release version if allowed.
+    fn matches(&self, requirement: &VersionReq, version: &Version) -> bool {
+        requirement.matches(version)
+            || (self.allow_prerelease
+                && !version.pre.is_empty()
+                && requirement.matches(&Version::new(version.major, version.minor, version.patch)))
+    }
+
+    /// Returns the hash of the fleet chart values, used to detect changes.
+    #[must_use]
+    pub fn values_hash(&self) -> String {
+        Sha256::digest(self.values().to_string().as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
     /// Adds the fleet helm repository.
     ///
     /// # Errors
     ///
     /// This function will return an error if the helm command fails to spawn.
     pub async fn add_repo(&self) -> RepoAddResult<()> {
+        let ChartSource::Repository(url) = &self.source else {
+            debug!("Using OCI chart source, skipping helm repository add");
+            return Ok(());
+        };
+
         let req = AddRequest {
             name: "fleet".to_string(),
-            url: self.repo.clone(),
+            url: url.clone(),
+            username: self.auth.username.clone(),
+            password: self.auth.password.clone(),
+            ca_file: self.auth.ca_file.clone(),
+            force_update: true,
             ..Default::default()
         };
 
@@ -99,83 +387,68 @@ impl FleetChart {
         Ok(())
     }
 
-    /// Searches the fleet helm repository for charts.
+    /// Lists all versions of the charts from the fleet repository index, cached by `add_repo`.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the helm command fails to spawn or the output cannot be parsed.
-    pub async fn search_repo(&self) -> RepoSearchResult<Vec<ChartSearch>> {
-        let req = SearchRequest {
-            terms: vec!["fleet".to_string()],
-            ..Default::default()
-        };
-
-        debug!("Searching fleet helm repository");
-
-        let res = HelmCallImpl::repo_search(req).await;
-        if let Some(err) = res.0.err.first() {
-            return Err(RepoSearchError::RepoSearch(err.clone()));
+    /// This function will return an error if the index cannot be read or parsed.
+    pub fn search_repo(&self) -> RepoSearchResult<Vec<ChartSearch>> {
+        if let ChartSource::Oci(_) = &self.source {
+            // OCI registries can't be searched, so only the requested version is known
+            let version = match self.version.clone().unwrap_or_default().normalized() {
+                Install::FollowLatest(_) | Install::Constraint(_) => String::new(),
+                Install::Version(version) => version,
+            };
+            return Ok(["fleet", "fleet-crd"]
+                .map(|name| ChartSearch {
+                    name: format!("fleet/{name}"),
+                    chart: EmbeddedChart {
+                        metadata: Metadata {
+                            app_version: version.clone(),
+                            name: name.into(),
+                            version: version.clone(),
+                        },
+                    },
+                })
+                .into());
         }
 
-        Ok(serde_json::from_str(&res.0.data)?)
+        let index = std::fs::read_to_string(storage::cached_index("fleet"))?;
+        storage::parse_index("fleet", &index)
     }
 
-    /// Gets metadata for a specific chart.
+    /// Gets the latest revision of the release from the helm storage secrets
+    /// in the chart namespace.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the helm command fails to spawn or the output cannot be parsed.
-    pub async fn get_metadata(chart: &str) -> MetadataGetResult<Option<ChartInfo>> {
-        let req = ListRequest {
-            all: true,
-            all_namespaces: true,
-            ..Default::default()
-        };
-
-        debug!("Listing helm charts metadata");
-
-        let res = HelmCallImpl::list(req).await;
-        if let Some(err) = res.0.err.first() {
-            return Err(MetadataGetError::MetadataGet(err.clone()));
-        }
-
-        if res.0.data.is_empty() {
-            return Ok(None);
-        }
-
-        let infos: Vec<ChartInfo> = serde_json::from_str(&res.0.data)?;
-
-        Ok(infos.into_iter().find(|i| i.name == chart))
+    /// This function will return an error if the secrets can't be listed or the release cannot be decoded.
+    pub async fn get_release(
+        &self,
+        client: Client,
+        release: &str,
+    ) -> MetadataGetResult<Option<ChartInfo>> {
+        debug!("Reading {release} helm release");
+
+        let secrets = Api::<Secret>::namespaced(client, &self.namespace)
+            .list(&ListParams::default().labels(&storage::release_selector(release)))
+            .await?;
+        storage::latest_release(&secrets.items)
     }
 
     /// Installs the fleet chart.
-    pub async fn install_fleet(&self) -> FleetInstallResult<ChartInfo> {
+    pub async fn install_fleet(&self, version: &str) -> FleetInstallResult<ChartInfo> {
         let req = InstallRequest {
             release_name: "fleet".to_string(),
-            chart: "fleet/fleet".to_string(),
+            chart: self.source.chart("fleet"),
+            username: self.auth.username.clone(),
+            password: self.auth.password.clone(),
+            ca_file: self.auth.ca_file.clone(),
             create_namespace: self.create_namespace,
             wait: self.wait,
             ns: self.namespace.clone(),
-            version: match self.version.clone().unwrap_or_default() {
-                Install::FollowLatest(_) => String::new(),
-                Install::Version(version) => version,
-            },
-            values: serde_json::to_vec(&json!({
-                "bootstrap": {
-                    "enabled": self.bootstrap_local_cluster.to_string(),
-                },
-                "extraEnv": [
-                    {
-                        "name": "EXPERIMENTAL_OCI_STORAGE",
-                        "value": self.feature_gates.experimental_oci_storage.to_string()
-                    },
-                    {
-                        "name": "EXPERIMENTAL_HELM_OPS",
-                        "value": self.feature_gates.experimental_helm_ops.to_string()
-                    }
-                ]
-            }))
-            .unwrap(),
+            version: version.to_string(),
+            values: serde_json::to_vec(&self.values())?,
             ..Default::default()
         };
 
@@ -190,32 +463,17 @@ impl FleetChart {
     }
 
     /// Upgrades the fleet chart.
-    pub async fn upgrade_fleet(&self) -> FleetInstallResult<ChartInfo> {
+    pub async fn upgrade_fleet(&self, version: &str) -> FleetInstallResult<ChartInfo> {
         let req = UpgradeRequest {
             release_name: "fleet".to_string(),
-            chart: "fleet/fleet".to_string(),
+            chart: self.source.chart("fleet"),
+            username: self.auth.username.clone(),
+            password: self.auth.password.clone(),
+            ca_file: self.auth.ca_file.clone(),
             wait: self.wait,
             ns: self.namespace.clone(),
-            reuse_values: true,
-            version: match self.version.clone().unwrap_or_default() {
-                Install::FollowLatest(_) => String::new(),
-                Install::Version(version) => version,
-            },
-            values: serde_json::to_vec(&json!({
-                "bootstrap": {
-                    "enabled": self.bootstrap_local_cluster.to_string(),
-                },
-                "extraEnv": [
-                    {
-                        "name": "EXPERIMENTAL_OCI_STORAGE",
-                        "value": self.feature_gates.experimental_oci_storage.to_string()
-                    },
-                    {
-                        "name": "EXPERIMENTAL_HELM_OPS",
-                        "value": self.feature_gates.experimental_helm_ops.to_string()
-                    }
-                ]
-            }))?,
+            version: version.to_string(),
+            values: serde_json::to_vec(&self.values())?,
             ..Default::default()
         };
 
@@ -230,18 +488,18 @@ impl FleetChart {
     }
 
     /// Installs the fleet-crd chart.
-    pub async fn install_fleet_crds(&self) -> FleetCRDInstallResult<ChartInfo> {
+    pub async fn install_fleet_crds(&self, version: &str) -> FleetCRDInstallResult<ChartInfo> {
         let req = InstallRequest {
             release_name: "fleet-crd".to_string(),
-            chart: "fleet/fleet-crd".to_string(),
+            chart: self.source.chart("fleet-crd"),
+            username: self.auth.username.clone(),
+            password: self.auth.password.clone(),
+            ca_file: self.auth.ca_file.clone(),
             create_namespace: self.create_namespace,
             wait: self.wait,
             ns: self.namespace.clone(),
             timeout: vec![300],
-            version: match self.version.clone().unwrap_or_default() {
-                Install::FollowLatest(_) => String::new(),
-                Install::Version(version) => version,
-            },
+            version: version.to_string(),
             ..Default::default()
         };
 
@@ -256,17 +514,17 @@ impl FleetChart {
     }
 
     /// Upgrades the fleet-crd chart.
-    pub async fn upgrade_fleet_crds(&self) -> FleetCRDInstallResult<ChartInfo> {
+    pub async fn upgrade_fleet_crds(&self, version: &str) -> FleetCRDInstallResult<ChartInfo> {
         let req = UpgradeRequest {
             release_name: "fleet-crd".to_string(),
-            chart: "fleet/fleet-crd".to_string(),
+            chart: self.source.chart("fleet-crd"),
+            username: self.auth.username.clone(),
+            password: self.auth.password.clone(),
+            ca_file: self.auth.ca_file.clone(),
             reuse_values: true,
             wait: self.wait,
             ns: self.namespace.clone(),
-            version: match self.version.clone().unwrap_or_default() {
-                Install::FollowLatest(_) => String::new(),
-                Install::Version(version) => version,
-            },
+            version: version.to_string(),
             ..Default::default()
         };
 
@@ -280,3 +538,267 @@ impl FleetChart {
         Ok(serde_json::from_str(&res.0.data)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::api::fleet_addon_config::{FLEET_REPOSITORY, FeatureGates, FleetInstall, Install};
+
+    use super::{
+        Chart, ChartInfo, ChartSearch, ChartSource, EmbeddedChart, FleetChart, Info, Metadata,
+        Resolution, merge_values, values_drift,
+    };
+
+    #[test]
+    fn test_chart_source() {
+        let source = ChartSource::from(None);
+        assert_eq!(source, ChartSource::Repository(FLEET_REPOSITORY.into()));
+        assert_eq!(source.chart("fleet"), "fleet/fleet");
+
+        let install = FleetInstall {
+            repository: Some("https://charts.example.com/fleet".into()),
+            ..Default::default()
+        };
+        let source = ChartSource::from(Some(&install));
+        assert_eq!(source.chart("fleet-crd"), "fleet/fleet-crd");
+
+        let install = FleetInstall {
+            oci_repository: Some("oci://registry.example.com/charts/".into()),
+            ..install
+        };
+        let source = ChartSource::from(Some(&install));
+        assert_eq!(source.to_string(), "oci://registry.example.com/charts");
+        assert_eq!(
+            source.chart("fleet-crd"),
+            "oci://registry.example.com/charts/fleet-crd"
+        );
+    }
+
+    #[test]
+    fn test_merge_values() {
+        let mut values = json!({
+            "agentImage": {"repository": "rancher/fleet-agent", "tag": "v0.12.0"},
+            "replicas": 1,
+            "extraEnv": [{"name": "HTTP_PROXY", "value": "proxy:3128"}],
+        });
+        merge_values(
+            &mut values,
+            json!({
+                "agentImage": {"tag": "v0.12.1"},
+                "replicas": 2,
+                "extraEnv": [],
+            }),
+        );
+        assert_eq!(
+            values,
+            json!({
+                "agentImage": {"repository": "rancher/fleet-agent", "tag": "v0.12.1"},
+                "replicas": 2,
+                "extraEnv": [],
+            })
+        );
+    }
+
+    #[test]
+    fn test_chart_values() {
+        let chart = FleetChart {
+            bootstrap_local_cluster: true,
+            feature_gates: FeatureGates {
+                experimental_oci_storage: true,
+                experimental_helm_ops: false,
+                config_map: None,
+            },
+            values: json!({
+                "bootstrap": {"enabled": "false", "namespace": "fleet-local"},
+                "extraEnv": [
+                    {"name": "HTTP_PROXY", "value": "proxy:3128"},
+                    {"name": "EXPERIMENTAL_OCI_STORAGE", "value": "false"},
+                ],
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            chart.values(),
+            json!({
+                "bootstrap": {"enabled": "true", "namespace": "fleet-local"},
+                "extraEnv": [
+                    {"name": "HTTP_PROXY", "value": "proxy:3128"},
+                    {"name": "EXPERIMENTAL_OCI_STORAGE", "value": "true"},
+                    {"name": "EXPERIMENTAL_HELM_OPS", "value": "false"},
+                ],
+            })
+        );
+
+        let changed = FleetChart {
+            values: json!({"replicas": 2}),
+            ..chart.clone()
+        };
+        assert_ne!(chart.values_hash(), changed.values_hash());
+    }
+
+    #[test]
+    fn test_values_drift() {
+        let desired = json!({
+            "bootstrap": {"enabled": "true"},
+            "extraEnv": [{"name": "EXPERIMENTAL_HELM_OPS", "value": "true"}],
+        });
+        assert!(values_drift(&desired, &desired).is_empty());
+        assert_eq!(
+            values_drift(&serde_json::Value::Null, &desired),
+            vec!["bootstrap", "extraEnv"]
+        );
+
+        let installed = json!({
+            "bootstrap": {"enabled": "true"},
+            "extraEnv": [{"name": "EXPERIMENTAL_HELM_OPS", "value": "false"}],
+            "replicas": 2,
+        });
+        assert_eq!(
+            values_drift(&installed, &desired),
+            vec!["extraEnv", "replicas"]
+        );
+    }
+
+    fn search(chart: &str, version: &str) -> ChartSearch {
+        ChartSearch {
+            name: format!("fleet/{chart}"),
+            chart: EmbeddedChart {
+                metadata: Metadata {
+                    app_version: version.into(),
+                    name: chart.into(),
+                    version: version.into(),
+                },
+            },
+        }
+    }
+
+    fn installed(version: &str) -> ChartInfo {
+        ChartInfo {
+            name: "fleet".into(),
+            namespace: "cattle-fleet-system".into(),
+            chart: Chart {
+                metadata: Metadata {
+                    app_version: version.into(),
+                    name: "fleet".into(),
+                    version: version.into(),
+                },
+            },
+            info: Info {
+                status: "deployed".into(),
+            },
+            config: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let available = [
+            search("fleet", "0.11.5"),
+            search("fleet", "0.12.0"),
+            search("fleet", "0.12.3"),
+            search("fleet", "0.13.0-rc.1"),
+            search("fleet-crd", "0.12.3"),
+        ];
+        let chart = |install: Install| FleetChart {
+            version: Some(install),
+            ..Default::default()
+        };
+
+        let latest = chart(Install::FollowLatest(true));
+        assert_eq!(
+            latest.resolve("fleet", None, &available),
+            Resolution::Install("0.12.3".into())
+        );
+        assert_eq!(
+            latest.resolve("fleet", Some(&installed("0.12.0")), &available),
+            Resolution::Upgrade("0.12.3".into())
+        );
+        assert_eq!(
+            latest.resolve("fleet", Some(&installed("0.12.3")), &available),
+            Resolution::Keep
+        );
+        assert_eq!(
+            chart(Install::FollowLatest(false)).resolve(
+                "fleet",
+                Some(&installed("0.12.0")),
+                &available
+            ),
+            Resolution::Keep
+        );
+
+        let prerelease = FleetChart {
+            allow_prerelease: true,
+            ..latest.clone()
+        };
+        assert_eq!(
+            prerelease.resolve("fleet", None, &available),
+            Resolution::Install("0.13.0-rc.1".into())
+        );
+
+        let constraint = chart(Install::Constraint(">=0.11, <0.12".into()));
+        assert_eq!(
+            constraint.resolve("fleet", None, &available),
+            Resolution::Install("0.11.5".into())
+        );
+        assert_eq!(
+            constraint.resolve("fleet", Some(&installed("0.12.3")), &available),
+            Resolution::DowngradeBlocked {
+                installed: "0.12.3".into(),
+                selected: "0.11.5".into(),
+            }
+        );
+        let downgrade = FleetChart {
+            allow_downgrade: true,
+            ..constraint
+        };
+        assert_eq!(
+            downgrade.resolve("fleet", Some(&installed("0.12.3")), &available),
+            Resolution::Upgrade("0.11.5".into())
+        );
+
+        let pinned = chart(Install::Version("v0.12.0".into()));
+        assert_eq!(
+            pinned.resolve("fleet", Some(&installed("0.11.5")), &available),
+            Resolution::Upgrade("0.12.0".into())
+        );
+        assert_eq!(
+            pinned.resolve("fleet-crd", None, &available),
+            Resolution::Unavailable
+        );
+
+        let custom = chart(Install::Version("0.12.0_custom".into()));
+        let custom_available = [search("fleet", "0.12.0"), search("fleet", "v0.12.0_custom")];
+        assert_eq!(
+            custom.resolve("fleet", None, &custom_available),
+            Resolution::Install("v0.12.0_custom".into())
+        );
+        assert_eq!(
+            custom.resolve("fleet", Some(&installed("0.12.0")), &custom_available),
+            Resolution::Upgrade("v0.12.0_custom".into())
+        );
+        assert_eq!(
+            custom.resolve(
+                "fleet",
+                Some(&installed("0.12.0_custom")),
+                &custom_available
+            ),
+            Resolution::Keep
+        );
+        assert_eq!(
+            custom.resolve("fleet", None, &available),
+            Resolution::Unavailable
+        );
+
+        let oci = [search("fleet", "")];
+        assert_eq!(
+            latest.resolve("fleet", None, &oci),
+            Resolution::Install(String::new())
+        );
+        assert_eq!(
+            latest.resolve("fleet", Some(&installed("0.12.3")), &oci),
+            Resolution::Keep
+        );
+    }
+}
@@ -44,28 +44,41 @@ pub type RepoAddResult<T> = std::result::Result<T, RepoAddError>;
 pub enum RepoAddError {
     #[error("Fleet repo add error: {0}")]
     RepoAdd(String),
+
+    #[error("Fleet repo credentials fetch error: {0}")]
+    CredentialsFetch(#[from] kube::Error),
+
+    #[error("Fleet repo CA bundle write error: {0}")]
+    CaBundleWrite(#[from] io::Error),
 }
 
 pub type RepoSearchResult<T> = std::result::Result<T, RepoSearchError>;
 
 #[derive(Error, Debug)]
 pub enum RepoSearchError {
-    #[error("Fleet repo search error: {0}")]
-    RepoSearch(String),
+    #[error("Fleet repo index read error: {0}")]
+    IndexRead(#[from] io::Error),
 
-    #[error("Deserialize search error: {0}")]
-    DeserializeInfoError(#[from] serde_json::Error),
+    #[error("Deserialize index error: {0}")]
+    DeserializeIndexError(#[from] serde_yaml::Error),
 }
 
 pub type MetadataGetResult<T> = std::result::Result<T, MetadataGetError>;
 
 #[derive(Error, Debug)]
 pub enum MetadataGetError {
-    #[error("Metadata get error: {0}")]
-    MetadataGet(String),
+    #[error("Release secrets lookup error: {0}")]
+    Lookup(#[from] kube::Error),
+
+    #[error("Release decode error: {0}")]
+    Decode(#[from] base64::DecodeError),
+
+    #[error("Release decompress error: {0}")]
+    Decompress(#[from] io::Error),
 
     #[error("Deserialize info error: {0}")]
     DeserializeInfoError(#[from] serde_json::Error),
 }
 
 pub mod install;
+pub mod storage;
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, env, io::Read as _, path::PathBuf};
+
+use base64::prelude::*;
+use flate2::read::GzDecoder;
+use k8s_openapi::api::core::v1::Secret;
+use serde::Deserialize;
+
+use super::{
+    MetadataGetResult, RepoSearchResult,
+    install::{ChartInfo, ChartSearch, EmbeddedChart, Metadata},
+};
+
+/// Magic header of the gzip compressed release data.
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+
+/// Label selector of the helm storage secrets for the release.
+#[must_use]
+pub fn release_selector(release: &str) -> String {
+    format!("owner=helm,name={release}")
+}
+
+/// Decodes the release stored by the helm secrets driver, which is base64 encoded
+/// and gzip compressed JSON.
+///
+/// # Errors
+///
+/// This function will return an error if the release data can't be decoded or parsed.
+pub fn decode_release(data: &[u8]) -> MetadataGetResult<ChartInfo> {
+    let data = BASE64_STANDARD.decode(data)?;
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(serde_json::from_slice(&data)?);
+    }
+
+    let mut release = vec![];
+    GzDecoder::new(data.as_slice()).read_to_end(&mut release)?;
+    Ok(serde_json::from_slice(&release)?)
+}
+
+/// Decodes the latest revision of the release from the helm storage secrets.
+///
+/// # Errors
+///
+/// This function will return an error if the release data can't be decoded or parsed.
+pub fn latest_release(secrets: &[Secret]) -> MetadataGetResult<Option<ChartInfo>> {
+    let revision = |secret: &&Secret| {
+        secret
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get("version")?.parse::<u32>().ok())
+    };
+
+    let data = secrets
+        .iter()
+        .max_by_key(revision)
+        .and_then(|secret| secret.data.as_ref()?.get("release"));
+    data.map(|data| decode_release(&data.0)).transpose()
+}
+
+#[derive(Deserialize)]
+struct IndexFile {
+    #[serde(default)]
+    entries: BTreeMap<String, Vec<Metadata>>,
+}
+
+/// Parses the repository `index.yaml` into the chart versions, named as `{repo}/{chart}`.
+///
+/// # Errors
+///
+/// This function will return an error if the index can't be parsed.
+pub fn parse_index(repo: &str, index: &str) -> RepoSearchResult<Vec<ChartSearch>> {
+    let index: IndexFile = serde_yaml::from_str(index)?;
+    Ok(index
+        .entries
+        .into_iter()
+        .flat_map(|(name, versions)| {
+            versions.into_iter().map(move |metadata| ChartSearch {
+                name: format!("{repo}/{name}"),
+                chart: EmbeddedChart { metadata },
+            })
+        })
+        .collect())
+}
+
+/// Returns the path of the repository index cached by `helm repo add`, following
+/// the helm environment settings.
+#[must_use]
+pub fn cached_index(repo: &str) -> PathBuf {
+    let cache = env::var("HELM_REPOSITORY_CACHE").map_or_else(
+        |_| {
+            env::var("HELM_CACHE_HOME")
+                .map(PathBuf::from)
+                .or_else(|_| {
+                    env::var("XDG_CACHE_HOME").map(|cache| PathBuf::from(cache).join("helm"))
+                })
+                .unwrap_or_else(|_| {
+                    PathBuf::from(env::var("HOME").unwrap_or_default()).join(".cache/helm")
+                })
+                .join("repository")
+        },
+        PathBuf::from,
+    );
+
+    cache.join(format!("{repo}-index.yaml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use base64::prelude::*;
+    use flate2::{Compression, write::GzEncoder};
+    use k8s_openapi::{ByteString, api::core::v1::Secret};
+    use kube::api::ObjectMeta;
+    use serde_json::json;
+
+    use super::{decode_release, latest_release, parse_index};
+
+    fn release(revision: u32, app_version: &str) -> Secret {
+        let release = json!({
+            "name": "fleet",
+            "namespace": "cattle-fleet-system",
+            "version": revision,
+            "info": {"status": "deployed"},
+            "chart": {"metadata": {"name": "fleet", "version": app_version, "appVersion": app_version}},
+            "config": {"bootstrap": {"enabled": "true"}},
+        });
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(release.to_string().as_bytes()).unwrap();
+        let data = BASE64_STANDARD.encode(encoder.finish().unwrap());
+
+        Secret {
+            metadata: ObjectMeta {
+                name: Some(format!("sh.helm.release.v1.fleet.v{revision}")),
+                labels: Some([("version".to_string(), revision.to_string())].into()),
+                ..Default::default()
+            },
+            data: Some([("release".to_string(), ByteString(data.into_bytes()))].into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_release() {
+        let plain = BASE64_STANDARD.encode(
+            json!({
+                "name": "fleet-crd",
+                "namespace": "cattle-fleet-system",
+                "info": {"status": "deployed"},
+            })
+            .to_string(),
+        );
+        let info = decode_release(plain.as_bytes()).unwrap();
+        assert_eq!(info.name, "fleet-crd");
+        assert!(info.config.is_null());
+
+        let secrets = [
+            release(1, "0.12.0"),
+            release(3, "0.12.3"),
+            release(2, "0.12.1"),
+        ];
+        let info = latest_release(&secrets).unwrap().unwrap();
+        assert_eq!(info.chart.metadata.app_version, "0.12.3");
+        assert_eq!(info.config, json!({"bootstrap": {"enabled": "true"}}));
+
+        assert!(latest_release(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let index = r"
+apiVersion: v1
+entries:
+  fleet:
+  - apiVersion: v2
+    appVersion: 0.12.3
+    name: fleet
+    version: 0.12.3
+    urls:
+    - https://github.com/rancher/fleet/releases/download/v0.12.3/fleet-0.12.3.tgz
+  - appVersion: 0.12.0
+    name: fleet
+    version: 0.12.0
+  fleet-crd:
+  - appVersion: 0.12.3
+    name: fleet-crd
+    version: 0.12.3
+generated: '2025-06-01T00:00:00Z'
+";
+        let charts = parse_index("fleet", index).unwrap();
+        let versions: Vec<_> = charts
+            .iter()
+            .map(|c| (c.name.as_str(), c.chart.metadata.version.as_str()))
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                ("fleet/fleet", "0.12.3"),
+                ("fleet/fleet", "0.12.0"),
+                ("fleet/fleet-crd", "0.12.3"),
+            ]
+        );
+
+        assert!(parse_index("fleet", "entries: []").is_err());
+    }
+}
@@ -42,6 +42,56 @@ pub enum ClusterSyncError {
 
     #[error("Cluster json encoding error: {0}")]
     ClusterEncodeError(#[from] serde_json::Error),
+
+    #[error("Cluster BundleDeployment lookup error: {0}")]
+    BundleDeploymentLookupError(#[source] kube::Error),
+
+    #[error("Cluster addons condition update error: {0}")]
+    AddonsConditionError(#[source] kube::Error),
+
+    #[error("Fleet cluster stale metadata removal error: {0}")]
+    StaleMetadataError(#[source] kube::Error),
+
+    #[error("Fleet cluster template values size guard error: {0}")]
+    TemplateValuesError(#[source] kube::Error),
+
+    #[error("Fleet cluster import removal error: {0}")]
+    ImportRemovalError(#[source] kube::Error),
+
+    #[error("Cluster registration token sync error: {0}")]
+    RegistrationTokenError(#[source] kube::Error),
+
+    #[error("Fleet agent bootstrap error: {0}")]
+    AgentBootstrapError(#[from] AgentBootstrapError),
+}
+
+pub type AgentBootstrapResult<T, E = AgentBootstrapError> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum AgentBootstrapError {
+    #[error("Agent registration lookup error: {0}")]
+    Lookup(#[source] kube::Error),
+
+    #[error("Kubeconfig secret `{0}` has no `value` key")]
+    MissingKubeconfig(String),
+
+    #[error("Workload cluster kubeconfig error: {0}")]
+    Kubeconfig(#[from] kube::config::KubeconfigError),
+
+    #[error("Agent manifests apply error: {0}")]
+    Apply(#[source] kube::Error),
+
+    #[error("Agent bootstrap removal error: {0}")]
+    Delete(#[source] kube::Error),
+
+    #[error("Secret `{0}` exists and is not owned by the agent bootstrap")]
+    UnownedSecret(String),
+
+    #[error("Agent registration values error: {0}")]
+    Values(#[from] serde_yaml::Error),
+
+    #[error("Agent manifests encoding error: {0}")]
+    Encode(#[from] serde_json::Error),
 }
 
 pub type GroupSyncResult<T, E = GroupSyncError> = std::result::Result<T, E>;
@@ -56,6 +106,21 @@ pub enum GroupSyncError {
 
     #[error("Unable to find origin ClusterClass for the ClusterGroup: {0}")]
     ClassLookup(#[from] kube::Error),
+
+    #[error("Cluster group status lookup error: {0}")]
+    StatusLookup(#[source] kube::Error),
+
+    #[error("Cluster group status encoding error: {0}")]
+    StatusEncodeError(#[from] serde_json::Error),
+
+    #[error("ClusterClass condition update error: {0}")]
+    ConditionError(#[source] kube::Error),
+
+    #[error("{0}")]
+    Config(#[from] ConfigFetchError),
+
+    #[error("Cluster group stale labels removal error: {0}")]
+    StaleLabelsError(#[source] kube::Error),
 }
 
 pub type GetOrCreateResult<T, E = GetOrCreateError> = std::result::Result<T, E>;
@@ -109,6 +174,50 @@ pub enum BundleError {
 
     #[error("BundleNamespaceMapping creating error: {0}")]
     Mapping(#[from] BundleMappingError),
+
+    #[error("Cluster annotation override error: {0}")]
+    ClusterOverride(#[from] ClusterOverrideError),
+}
+
+pub type ClusterOverrideResult<T> = std::result::Result<T, ClusterOverrideError>;
+
+#[derive(Error, Debug)]
+pub enum ClusterOverrideError {
+    #[error("Invalid boolean value in `{0}` annotation: {1}")]
+    Bool(String, #[source] std::str::ParseBoolError),
+
+    #[error("Invalid tolerations in `{0}` annotation: {1}")]
+    Tolerations(String, #[source] serde_yaml::Error),
+}
+
+pub type TemplateSourceResult<T> = std::result::Result<T, TemplateSourceError>;
+
+#[derive(Error, Debug)]
+pub enum TemplateSourceError {
+    #[error("Template source `{0}` reference is incomplete")]
+    IncompleteReference(String),
+
+    #[error("Template source `{0}` lookup error: {1}")]
+    Lookup(String, #[source] kube::Error),
+
+    #[error("Template source `{0}` selector error: {1}")]
+    Selector(String, #[source] kube::core::ParseExpressionError),
+
+    #[error("Template values encoding error: {0}")]
+    Encode(#[from] serde_json::Error),
+}
+
+impl TemplateSourceError {
+    /// Returns the template values key of the failed source, if any.
+    #[must_use]
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::IncompleteReference(key) | Self::Lookup(key, _) | Self::Selector(key, _) => {
+                Some(key)
+            }
+            Self::Encode(_) => None,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -117,6 +226,23 @@ pub enum BundleMappingError {
     ClusterClassLookup(#[from] kube::Error),
 }
 
+pub type GarbageCollectionResult<T> = std::result::Result<T, GarbageCollectionError>;
+
+#[derive(Error, Debug)]
+pub enum GarbageCollectionError {
+    #[error("Orphaned objects lookup error: {0}")]
+    Lookup(#[source] kube::Error),
+
+    #[error("Orphaned object removal error: {0}")]
+    Delete(#[source] kube::Error),
+
+    #[error("Orphaned object event error: {0}")]
+    Event(#[source] kube::Error),
+
+    #[error("Legacy BundleNamespaceMapping labeling error: {0}")]
+    Migration(#[source] kube::Error),
+}
+
 pub type ConfigFetchResult<T> = std::result::Result<T, ConfigFetchError>;
 
 #[derive(Error, Debug)]
@@ -126,8 +252,11 @@ pub enum ConfigFetchError {
 }
 
 pub mod addon_config;
+#[cfg(feature = "agent-initiated")]
+pub mod agent_bootstrap;
 pub mod cluster;
 pub mod cluster_class;
 pub mod cluster_group;
 pub mod controller;
+pub mod garbage_collection;
 pub mod helm;
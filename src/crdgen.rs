@@ -1,9 +1,20 @@
-use ::controller::api::fleet_addon_config::FleetAddonConfig;
-use kube::CustomResourceExt;
+use ::controller::api::{
+    fleet_addon_config::{FleetAddonConfig, v1alpha2},
+    fleet_addon_config_override::FleetAddonConfigOverride,
+};
+use kube::{CustomResourceExt, core::crd::merge_crds};
 
 fn main() {
+    // v1alpha1 remains the storage version, v1alpha2 is served via the conversion webhook
+    let crd = merge_crds(
+        vec![FleetAddonConfig::crd(), v1alpha2::FleetAddonConfig::crd()],
+        "v1alpha1",
+    )
+    .expect("FleetAddonConfig versions to be compatible");
+
+    print!("{}", serde_yaml::to_string(&crd).unwrap());
     print!(
-        "{}",
-        serde_yaml::to_string(&FleetAddonConfig::crd()).unwrap()
+        "---\n{}",
+        serde_yaml::to_string(&FleetAddonConfigOverride::crd()).unwrap()
     );
 }
@@ -1,9 +1,10 @@
 use std::io;
 
 use controllers::{
-    BundleError, SyncError,
+    BundleError, GarbageCollectionError, SyncError,
     addon_config::{
-        AddonConfigSyncError, DynamicWatcherError, FleetPatchError, ReconcileConfigSyncError,
+        AddonConfigSyncError, ChartValuesError, DynamicWatcherError, FleetPatchError,
+        ReconcileConfigSyncError,
     },
 };
 use futures::channel::mpsc::TrySendError;
@@ -32,6 +33,9 @@ pub enum Error {
     #[error("Fleet repo add error: {0}")]
     RepoAdd(#[from] helm::RepoAddError),
 
+    #[error("Fleet chart values error: {0}")]
+    ChartValues(#[from] ChartValuesError),
+
     #[error("Error waiting for commadnd: {0}")]
     CommandError(#[from] io::Error),
 
@@ -41,6 +45,9 @@ pub enum Error {
     #[error("Reconcile config sync error: {0}")]
     ReconcileConfigSync(#[from] ReconcileConfigSyncError),
 
+    #[error("Garbage collection error: {0}")]
+    GarbageCollection(#[from] GarbageCollectionError),
+
     #[error("Namespace trigger error: {0}")]
     TriggerError(#[from] TrySendError<()>),
 
@@ -74,6 +81,9 @@ pub mod telemetry;
 mod metrics;
 pub use metrics::Metrics;
 
+/// Webhooks served by the controller
+pub mod webhooks;
+
 /*
 #[cfg(test)] pub mod fixtures;
 */
@@ -1,9 +1,14 @@
 use actix_web::{
-    App, HttpRequest, HttpResponse, HttpServer, Responder, get, middleware, web::Data,
+    App, HttpRequest, HttpResponse, HttpServer, Responder, get, middleware, post,
+    web::{Data, Json},
+};
+pub use controller::{self, State, api::capi_cluster::ClusterApiVersion, telemetry, webhooks};
+use kube::{
+    Client,
+    core::{DynamicObject, admission::AdmissionReview, conversion::ConversionReview},
 };
-pub use controller::{self, State, telemetry};
-use kube::Client;
 use prometheus::{Encoder, TextEncoder};
+use tracing::info;
 
 #[get("/metrics")]
 async fn metrics(c: Data<State>, _req: HttpRequest) -> impl Responder {
@@ -25,6 +30,26 @@ async fn index(c: Data<State>, _req: HttpRequest) -> impl Responder {
     HttpResponse::Ok().json(&d)
 }
 
+#[post("/convert")]
+async fn convert(review: Json<ConversionReview>) -> impl Responder {
+    HttpResponse::Ok().json(webhooks::conversion::review(review.into_inner()))
+}
+
+#[post("/validate-addons-cluster-x-k8s-io-v1alpha1-fleetaddonconfig")]
+async fn validate(review: Json<AdmissionReview<DynamicObject>>) -> impl Responder {
+    HttpResponse::Ok().json(webhooks::admission::validate(review.into_inner()))
+}
+
+#[post("/validate-addons-cluster-x-k8s-io-v1alpha1-fleetaddonconfigoverride")]
+async fn validate_override(review: Json<AdmissionReview<DynamicObject>>) -> impl Responder {
+    HttpResponse::Ok().json(webhooks::admission::validate(review.into_inner()))
+}
+
+#[post("/mutate-addons-cluster-x-k8s-io-v1alpha1-fleetaddonconfig")]
+async fn mutate(review: Json<AdmissionReview<DynamicObject>>) -> impl Responder {
+    HttpResponse::Ok().json(webhooks::admission::mutate(review.into_inner()))
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     telemetry::init();
@@ -48,9 +73,16 @@ async fn main() -> anyhow::Result<()> {
         let helm_install_controller = controller::run_fleet_helm_controller(state.clone());
         tokio::join!(helm_install_controller);
     } else {
+        let capi_version = ClusterApiVersion::discover(&client)
+            .await
+            .expect("discover served Cluster API version");
+        info!("Using Cluster API version {}", capi_version.as_str());
+        capi_version.serve();
+
         let fleet_config_controller = controller::run_fleet_addon_config_controller(state.clone());
         let cluster_controller = controller::run_cluster_controller(state.clone());
         let cluster_class_controller = controller::run_cluster_class_controller(state.clone());
+        let flags = state.flags.clone();
 
         // Start web server
         let server = HttpServer::new(move || {
@@ -65,13 +97,42 @@ async fn main() -> anyhow::Result<()> {
         .shutdown_timeout(5)
         .run();
 
-        tokio::join!(
+        // Start webhook server
+        let webhook_server = match flags.webhook_cert_dir.as_deref() {
+            Some(cert_dir) => Some(
+                HttpServer::new(move || {
+                    App::new()
+                        .wrap(middleware::Logger::default())
+                        .service(convert)
+                        .service(validate)
+                        .service(validate_override)
+                        .service(mutate)
+                })
+                .bind_rustls_0_23(
+                    ("0.0.0.0", flags.webhook_port),
+                    webhooks::tls_config(cert_dir)?,
+                )?
+                .shutdown_timeout(5)
+                .run(),
+            ),
+            None => None,
+        };
+        let webhook_server = async {
+            match webhook_server {
+                Some(server) => server.await,
+                None => Ok(()),
+            }
+        };
+
+        let (_, _, _, server, webhook_server) = tokio::join!(
             cluster_controller,
             cluster_class_controller,
             fleet_config_controller,
-            server
-        )
-        .3?;
+            server,
+            webhook_server
+        );
+        server?;
+        webhook_server?;
     }
     Ok(())
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::Error;
+use crate::controllers::TemplateSourceError;
 use chrono::{DateTime, Utc};
 use kube::{
     Client, ResourceExt,
@@ -14,6 +15,7 @@ use tokio::time::Instant;
 pub struct Metrics {
     pub reconciliations: IntCounter,
     pub failures: IntCounterVec,
+    pub template_failures: IntCounterVec,
     pub reconcile_duration: HistogramVec,
 }
 
@@ -36,11 +38,20 @@ impl Default for Metrics {
             &["instance", "error"],
         )
         .unwrap();
+        let template_failures = IntCounterVec::new(
+            opts!(
+                "caapf_controller_template_resolution_errors_total",
+                "template values resolution errors",
+            ),
+            &["namespace", "instance", "source"],
+        )
+        .unwrap();
         let reconciliations =
             IntCounter::new("caapf_controller_reconciliations_total", "reconciliations").unwrap();
         Metrics {
             reconciliations,
             failures,
+            template_failures,
             reconcile_duration,
         }
     }
@@ -59,6 +70,7 @@ impl Metrics {
     pub fn register(self, registry: &Registry) -> Result<Self, prometheus::Error> {
         registry.register(Box::new(self.reconcile_duration.clone()))?;
         registry.register(Box::new(self.failures.clone()))?;
+        registry.register(Box::new(self.template_failures.clone()))?;
         registry.register(Box::new(self.reconciliations.clone()))?;
         Ok(self)
     }
@@ -70,6 +82,16 @@ impl Metrics {
             .inc();
     }
 
+    pub fn template_failure<C: kube::Resource>(&self, obj: &C, e: &TemplateSourceError) {
+        self.template_failures
+            .with_label_values(&[
+                obj.namespace().unwrap_or_default().as_str(),
+                obj.name_any().as_str(),
+                e.key().unwrap_or_default(),
+            ])
+            .inc();
+    }
+
     #[must_use]
     pub fn count_and_measure(&self) -> ReconcileMeasurer {
         self.reconciliations.inc();
@@ -0,0 +1,651 @@
+use std::collections::HashSet;
+
+use kube::core::{
+    DynamicObject, ParseExpressionError, Selector,
+    admission::{AdmissionRequest, AdmissionResponse, AdmissionReview},
+    dynamic::ParseDynamicObjectError,
+};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::api::fleet_addon_config::{
+    ClusterGroupRule, FleetAddonConfig, Install, MetadataFilter, RESERVED_TEMPLATE_KEYS, Server,
+};
+use crate::api::fleet_addon_config_override::FleetAddonConfigOverride;
+
+pub type AdmissionResult<T> = std::result::Result<T, AdmissionError>;
+
+#[derive(Error, Debug)]
+pub enum AdmissionError {
+    #[error("Object is not a valid {0}: {1}")]
+    Parse(String, #[source] ParseDynamicObjectError),
+
+    #[error("Object serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Invalid configuration: {0}")]
+    Validation(#[from] ValidationError),
+}
+
+pub type ValidationResult<T> = std::result::Result<T, ValidationError>;
+
+#[derive(Error, Debug)]
+pub enum ValidationError {
+    #[error("cluster selector is invalid: {0}")]
+    ClusterSelector(#[source] ParseExpressionError),
+
+    #[error("namespace selector is invalid: {0}")]
+    NamespaceSelector(#[source] ParseExpressionError),
+
+    #[error("install version `{0}` is not a valid semver version: {1}")]
+    InstallVersion(String, #[source] semver::Error),
+
+    #[error("install constraint `{0}` is not a valid semver requirement: {1}")]
+    InstallConstraint(String, #[source] semver::Error),
+
+    #[error("install constraint requires a Helm repository, as OCI registries can't be searched")]
+    OciConstraint,
+
+    #[error("install followLatest requires a Helm repository, as OCI registries can't be searched")]
+    OciFollowLatest,
+
+    #[error("install OCI repository `{0}` must use the oci:// scheme")]
+    OciRepository(String),
+
+    #[error("custom server config requires apiServerUrl or apiServerCaConfigRef to be set")]
+    EmptyServer,
+
+    #[error("propagation key pattern is invalid: {0}")]
+    KeyPattern(#[source] regex::Error),
+
+    #[error("template source key `{0}` is reserved or used more than once")]
+    TemplateSourceKey(String),
+
+    #[error("template source `{0}` references a Secret and requires the exposed keys")]
+    SecretTemplateSourceKeys(String),
+
+    #[error("cluster group rule name `{0}` is not a valid DNS label or used more than once")]
+    GroupRuleName(String),
+
+    #[error("cluster group rule `{0}` selector is invalid: {1}")]
+    GroupRuleSelector(String, #[source] ParseExpressionError),
+
+    #[cfg(feature = "agent-initiated")]
+    #[error("registration token TTL `{0}` is not a positive duration like `24h`")]
+    RegistrationTokenTtl(String),
+}
+
+impl FleetAddonConfig {
+    /// Validates settings which are otherwise only checked at reconcile time.
+    pub(crate) fn validate(&self) -> ValidationResult<()> {
+        self.cluster_selector()
+            .map_err(ValidationError::ClusterSelector)?;
+        self.namespace_selector()
+            .map_err(ValidationError::NamespaceSelector)?;
+
+        let filters = self
+            .spec
+            .cluster
+            .iter()
+            .flat_map(|cluster| {
+                cluster
+                    .label_propagation
+                    .iter()
+                    .chain(cluster.annotation_propagation.iter())
+            })
+            .chain(self.cluster_class_label_filter());
+        validate_filters(filters)?;
+
+        let sources = self
+            .spec
+            .cluster
+            .iter()
+            .flat_map(|cluster| cluster.template_sources.iter().flatten());
+        let mut keys = HashSet::from(RESERVED_TEMPLATE_KEYS);
+        for source in sources {
+            if !keys.insert(source.key.as_str()) {
+                return Err(ValidationError::TemplateSourceKey(source.key.clone()));
+            }
+            if source.is_secret() && source.keys.is_none() {
+                return Err(ValidationError::SecretTemplateSourceKeys(
+                    source.key.clone(),
+                ));
+            }
+        }
+
+        validate_group_rules(
+            self.spec
+                .cluster
+                .iter()
+                .flat_map(|cluster| cluster.cluster_groups.iter().flatten()),
+        )?;
+
+        #[cfg(feature = "agent-initiated")]
+        validate_token_ttl(
+            self.spec
+                .cluster
+                .as_ref()
+                .and_then(|cluster| cluster.registration_token_ttl.as_deref()),
+        )?;
+
+        let install_version = self
+            .spec
+            .install
+            .as_ref()
+            .map(|install| install.install_version.clone().normalized());
+        match install_version {
+            Some(Install::Version(version)) => {
+                semver::Version::parse(&version)
+                    .map_err(|e| ValidationError::InstallVersion(version.clone(), e))?;
+            }
+            Some(Install::Constraint(constraint)) => {
+                semver::VersionReq::parse(&constraint)
+                    .map_err(|e| ValidationError::InstallConstraint(constraint.clone(), e))?;
+            }
+            _ => {}
+        }
+
+        let oci_repository = self
+            .spec
+            .install
+            .as_ref()
+            .and_then(|install| install.oci_repository.as_ref());
+        if let Some(reference) = oci_repository {
+            if !reference.starts_with("oci://") {
+                return Err(ValidationError::OciRepository(reference.clone()));
+            }
+            match self
+                .spec
+                .install
+                .as_ref()
+                .map(|install| &install.install_version)
+            {
+                Some(Install::Constraint(_)) => return Err(ValidationError::OciConstraint),
+                Some(Install::FollowLatest(true)) => return Err(ValidationError::OciFollowLatest),
+                _ => {}
+            }
+        }
+
+        match self
+            .spec
+            .config
+            .as_ref()
+            .and_then(|config| config.server.as_ref())
+        {
+            Some(Server::Custom(options))
+                if options.api_server_url.is_none()
+                    && options.api_server_ca_config_ref.is_none() =>
+            {
+                Err(ValidationError::EmptyServer)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FleetAddonConfigOverride {
+    /// Validates the overridden settings with the checks applied to the global config.
+    pub(crate) fn validate(&self) -> ValidationResult<()> {
+        let cluster = &self.spec.cluster;
+        validate_filters(
+            cluster
+                .label_propagation
+                .iter()
+                .chain(cluster.annotation_propagation.iter()),
+        )?;
+        validate_group_rules(cluster.cluster_groups.iter().flatten())?;
+
+        #[cfg(feature = "agent-initiated")]
+        validate_token_ttl(cluster.registration_token_ttl.as_deref())?;
+
+        Ok(())
+    }
+}
+
+/// Checks the key patterns of the propagation filters.
+fn validate_filters<'a>(filters: impl Iterator<Item = &'a MetadataFilter>) -> ValidationResult<()> {
+    for pattern in filters.flat_map(MetadataFilter::patterns) {
+        pattern.validate().map_err(ValidationError::KeyPattern)?;
+    }
+
+    Ok(())
+}
+
+/// Checks the group rules have unique names, usable in label keys, and valid selectors.
+fn validate_group_rules<'a>(
+    rules: impl Iterator<Item = &'a ClusterGroupRule>,
+) -> ValidationResult<()> {
+    let mut names = HashSet::new();
+    for rule in rules {
+        if !is_dns_label(&rule.name) || !names.insert(rule.name.as_str()) {
+            return Err(ValidationError::GroupRuleName(rule.name.clone()));
+        }
+        Selector::try_from(rule.selector.clone())
+            .map_err(|e| ValidationError::GroupRuleSelector(rule.name.clone(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Checks the registration token TTL is a positive duration, as a zero or negative TTL
+/// creates tokens which expire immediately, and unparsable values are rejected by Fleet.
+#[cfg(feature = "agent-initiated")]
+fn validate_token_ttl(ttl: Option<&str>) -> ValidationResult<()> {
+    match ttl {
+        Some(ttl) if !is_positive_duration(ttl) => {
+            Err(ValidationError::RegistrationTokenTtl(ttl.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks the Go duration format used by Fleet, like `1h30m`, for a positive duration.
+#[cfg(feature = "agent-initiated")]
+fn is_positive_duration(value: &str) -> bool {
+    // Longer units first, so `ms` and `ns` are not read as minutes or seconds
+    const UNITS: [&str; 8] = ["ns", "us", "µs", "μs", "ms", "h", "m", "s"];
+
+    let mut rest = value.strip_prefix('+').unwrap_or(value);
+    let mut positive = false;
+    while !rest.is_empty() {
+        let (number, tail) = rest.split_at(
+            rest.find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len()),
+        );
+        let Ok(number) = number.parse::<f64>() else {
+            return false;
+        };
+        let Some(unit) = UNITS.iter().find(|unit| tail.starts_with(*unit)) else {
+            return false;
+        };
+        positive |= number > 0.0;
+        rest = &tail[unit.len()..];
+    }
+
+    positive
+}
+
+/// Checks the RFC 1123 label format, required for names used in label keys.
+fn is_dns_label(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+}
+
+/// Handles a validating `AdmissionReview` for the `FleetAddonConfig` and
+/// `FleetAddonConfigOverride` resources.
+#[must_use]
+pub fn validate(review: AdmissionReview<DynamicObject>) -> AdmissionReview<DynamicObject> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid admission request: {e}");
+            return AdmissionResponse::invalid(e.to_string()).into_review();
+        }
+    };
+
+    let response = AdmissionResponse::from(&request);
+    let Some(object) = request.object.as_ref() else {
+        return response.into_review();
+    };
+
+    let kind = request.kind.kind.as_str();
+    let result = match kind {
+        "FleetAddonConfigOverride" => {
+            parse_override(object).and_then(|config_override| Ok(config_override.validate()?))
+        }
+        _ => parse(object).and_then(|config| Ok(config.validate()?)),
+    };
+    match result {
+        Ok(()) => response,
+        Err(e) => {
+            debug!("Denying {kind} {:?}: {e}", object.metadata.name);
+            response.deny(e.to_string())
+        }
+    }
+    .into_review()
+}
+
+/// Handles a mutating `AdmissionReview` for the `FleetAddonConfig` resource,
+/// responding with a patch which applies the default settings.
+#[must_use]
+pub fn mutate(review: AdmissionReview<DynamicObject>) -> AdmissionReview<DynamicObject> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid admission request: {e}");
+            return AdmissionResponse::invalid(e.to_string()).into_review();
+        }
+    };
+
+    let response = AdmissionResponse::from(&request);
+    let Some(object) = request.object.as_ref() else {
+        return response.into_review();
+    };
+
+    match defaults_patch(object) {
+        Ok(patch) => response
+            .with_patch(patch)
+            .unwrap_or_else(|e| AdmissionResponse::from(&request).deny(e.to_string())),
+        Err(e) => response.deny(e.to_string()),
+    }
+    .into_review()
+}
+
+fn parse(object: &DynamicObject) -> AdmissionResult<FleetAddonConfig> {
+    object
+        .clone()
+        .try_parse()
+        .map_err(|e| AdmissionError::Parse("FleetAddonConfig".into(), e))
+}
+
+fn parse_override(object: &DynamicObject) -> AdmissionResult<FleetAddonConfigOverride> {
+    object
+        .clone()
+        .try_parse()
+        .map_err(|e| AdmissionError::Parse("FleetAddonConfigOverride".into(), e))
+}
+
+/// Builds a JSON patch between the submitted object and the object with defaults applied.
+/// Only the defaulted values are changed, fields unknown to the typed config are kept as submitted.
+fn defaults_patch(object: &DynamicObject) -> AdmissionResult<json_patch::Patch> {
+    let config = parse(object)?;
+    let mut defaulted = config.clone();
+    defaulted.spec.apply_defaults();
+
+    let submitted = serde_json::to_value(object)?;
+    let mut desired = submitted.clone();
+    if let Some(changes) = changes(
+        &serde_json::to_value(&config)?,
+        &serde_json::to_value(&defaulted)?,
+    ) {
+        json_patch::merge(&mut desired, &changes);
+    }
+
+    Ok(json_patch::diff(&submitted, &desired))
+}
+
+/// Builds a JSON merge patch with the values changed between two objects.
+fn changes(from: &Value, to: &Value) -> Option<Value> {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let changed: Map<String, Value> = to
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((
+                        key.clone(),
+                        changes(from.get(key).unwrap_or(&Value::Null), value)?,
+                    ))
+                })
+                .chain(
+                    from.keys()
+                        .filter(|key| !to.contains_key(*key))
+                        .map(|key| (key.clone(), Value::Null)),
+                )
+                .collect();
+            (!changed.is_empty()).then_some(Value::Object(changed))
+        }
+        (from, to) => (from != to).then(|| to.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kube::core::DynamicObject;
+    use serde_json::json;
+
+    use super::{defaults_patch, parse, parse_override};
+
+    fn object(spec: serde_json::Value) -> DynamicObject {
+        serde_json::from_value(json!({
+            "apiVersion": "addons.cluster.x-k8s.io/v1alpha1",
+            "kind": "FleetAddonConfig",
+            "metadata": {"name": "fleet-addon-config"},
+            "spec": spec,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = object(json!({
+            "cluster": {
+                "namespaceSelector": {},
+                "selector": {"matchLabels": {"import": "true"}},
+            },
+            "install": {"version": "v0.12.0"},
+            "config": {"server": {"custom": {"apiServerUrl": "https://localhost:6443"}}},
+        }));
+        assert!(parse(&valid).unwrap().validate().is_ok());
+
+        let bad_selector = object(json!({
+            "cluster": {
+                "namespaceSelector": {},
+                "selector": {"matchExpressions": [{"key": "import", "operator": "Unknown"}]},
+            },
+        }));
+        assert!(parse(&bad_selector).unwrap().validate().is_err());
+
+        let bad_version = object(json!({"install": {"version": "latest"}}));
+        assert!(parse(&bad_version).unwrap().validate().is_err());
+
+        let oci = object(json!({
+            "install": {"version": "v0.12.0", "ociRepository": "oci://registry.example.com/charts"},
+        }));
+        assert!(parse(&oci).unwrap().validate().is_ok());
+
+        let oci_follow_latest = object(json!({
+            "install": {"followLatest": true, "ociRepository": "oci://registry.example.com/charts"},
+        }));
+        assert!(parse(&oci_follow_latest).unwrap().validate().is_err());
+
+        let constraint = object(json!({"install": {"constraint": ">=0.11, <0.13"}}));
+        assert!(parse(&constraint).unwrap().validate().is_ok());
+
+        let bad_constraint = object(json!({"install": {"constraint": "0.12 or later"}}));
+        assert!(parse(&bad_constraint).unwrap().validate().is_err());
+
+        let oci_constraint = object(json!({
+            "install": {"constraint": "~0.12", "ociRepository": "oci://registry.example.com/charts"},
+        }));
+        assert!(parse(&oci_constraint).unwrap().validate().is_err());
+
+        let bad_oci = object(json!({
+            "install": {"version": "v0.12.0", "ociRepository": "registry.example.com/charts"},
+        }));
+        assert!(parse(&bad_oci).unwrap().validate().is_err());
+
+        let empty_server = object(json!({"config": {"server": {"custom": {}}}}));
+        assert!(parse(&empty_server).unwrap().validate().is_err());
+
+        let bad_pattern = object(json!({
+            "cluster": {
+                "namespaceSelector": {},
+                "selector": {},
+                "labelPropagation": {"exclude": [{"regex": "("}]},
+            },
+        }));
+        assert!(parse(&bad_pattern).unwrap().validate().is_err());
+
+        let bad_class_pattern = object(json!({
+            "clusterClass": {"labelPropagation": {"include": [{"regex": "["}]}},
+        }));
+        assert!(parse(&bad_class_pattern).unwrap().validate().is_err());
+
+        let reserved_key = object(json!({
+            "cluster": {
+                "namespaceSelector": {},
+                "selector": {},
+                "templateSources": [{"key": "Cluster", "apiVersion": "v1", "kind": "ConfigMap"}],
+            },
+        }));
+        assert!(parse(&reserved_key).unwrap().validate().is_err());
+
+        let secret_source = |source: serde_json::Value| {
+            object(json!({
+                "cluster": {
+                    "namespaceSelector": {},
+                    "selector": {},
+                    "templateSources": [source],
+                },
+            }))
+        };
+        let whole_secret = secret_source(
+            json!({"key": "Credentials", "apiVersion": "v1", "kind": "Secret", "name": "creds"}),
+        );
+        assert!(parse(&whole_secret).unwrap().validate().is_err());
+        let secret_keys = secret_source(json!({
+            "key": "Credentials",
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "name": "creds",
+            "keys": ["region"],
+        }));
+        assert!(parse(&secret_keys).unwrap().validate().is_ok());
+
+        let group_rules = |rules: serde_json::Value| {
+            object(json!({
+                "cluster": {
+                    "namespaceSelector": {},
+                    "selector": {},
+                    "clusterGroups": rules,
+                },
+            }))
+        };
+        let valid_rules = group_rules(json!([
+            {"name": "edge", "selector": {"matchLabels": {"location": "edge"}}},
+        ]));
+        assert!(parse(&valid_rules).unwrap().validate().is_ok());
+
+        let duplicate_rules = group_rules(json!([
+            {"name": "edge", "selector": {}},
+            {"name": "edge", "selector": {}},
+        ]));
+        assert!(parse(&duplicate_rules).unwrap().validate().is_err());
+
+        let bad_rule_name = group_rules(json!([{"name": "Edge.Clusters", "selector": {}}]));
+        assert!(parse(&bad_rule_name).unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_override() {
+        let config_override = |cluster: serde_json::Value| -> DynamicObject {
+            serde_json::from_value(json!({
+                "apiVersion": "addons.cluster.x-k8s.io/v1alpha1",
+                "kind": "FleetAddonConfigOverride",
+                "metadata": {"name": "fleet-addon-config", "namespace": "tenant-a"},
+                "spec": {"cluster": cluster},
+            }))
+            .unwrap()
+        };
+
+        let valid = config_override(json!({
+            "labelPropagation": {"include": [{"regex": "^tenant/"}]},
+            "clusterGroups": [{"name": "edge", "selector": {"matchLabels": {"location": "edge"}}}],
+        }));
+        assert!(parse_override(&valid).unwrap().validate().is_ok());
+
+        let bad_pattern = config_override(json!({
+            "annotationPropagation": {"exclude": [{"regex": "("}]},
+        }));
+        assert!(parse_override(&bad_pattern).unwrap().validate().is_err());
+
+        let bad_selector = config_override(json!({
+            "clusterGroups": [{
+                "name": "edge",
+                "selector": {"matchExpressions": [{"key": "location", "operator": "Unknown"}]},
+            }],
+        }));
+        assert!(parse_override(&bad_selector).unwrap().validate().is_err());
+
+        let bad_rule_name = config_override(json!({
+            "clusterGroups": [{"name": "Edge.Clusters", "selector": {}}],
+        }));
+        assert!(parse_override(&bad_rule_name).unwrap().validate().is_err());
+
+        #[cfg(feature = "agent-initiated")]
+        {
+            let ttl = config_override(json!({"registrationTokenTtl": "12h"}));
+            assert!(parse_override(&ttl).unwrap().validate().is_ok());
+
+            let bad_ttl = config_override(json!({"registrationTokenTtl": "0s"}));
+            assert!(parse_override(&bad_ttl).unwrap().validate().is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "agent-initiated")]
+    fn test_validate_token_ttl() {
+        let config = |ttl: &str| {
+            object(json!({
+                "cluster": {
+                    "namespaceSelector": {},
+                    "selector": {},
+                    "registrationTokenTtl": ttl,
+                },
+            }))
+        };
+
+        for valid in ["1h", "24h", "1h30m", "90s", "1.5h", "+10m", "500ms"] {
+            assert!(
+                parse(&config(valid)).unwrap().validate().is_ok(),
+                "{valid} should be accepted"
+            );
+        }
+        for invalid in [
+            "0", "0s", "0h0m", "-1h", "1d", "1", "h", "", "1h-30m", "1..5h",
+        ] {
+            assert!(
+                parse(&config(invalid)).unwrap().validate().is_err(),
+                "{invalid} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_defaults_patch() {
+        let config = object(json!({
+            "cluster": {
+                "hostNetwork": false,
+                "namespaceSelector": {},
+                "selector": {},
+            },
+            "clusterClass": {},
+        }));
+
+        let mut patched = serde_json::to_value(&config).unwrap();
+        json_patch::patch(&mut patched, &defaults_patch(&config).unwrap()).unwrap();
+
+        assert_eq!(patched["spec"]["cluster"]["hostNetwork"], false);
+        assert_eq!(patched["spec"]["cluster"]["applyClassGroup"], true);
+        assert_eq!(
+            patched["spec"]["cluster"]["agentNamespace"],
+            "fleet-addon-agent"
+        );
+        assert_eq!(patched["spec"]["clusterClass"]["patchResource"], true);
+        assert!(patched["spec"]["config"].is_null());
+
+        let unchanged = object(json!({}));
+        assert!(defaults_patch(&unchanged).unwrap().0.is_empty());
+
+        let unknown = object(json!({
+            "clusterClass": {"unknownField": "kept"},
+        }));
+        let mut patched = serde_json::to_value(&unknown).unwrap();
+        let patch = defaults_patch(&unknown).unwrap();
+        assert!(
+            patch
+                .0
+                .iter()
+                .all(|op| op.path().to_string().starts_with("/spec/clusterClass/"))
+        );
+        json_patch::patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched["spec"]["clusterClass"]["unknownField"], "kept");
+        assert_eq!(patched["spec"]["clusterClass"]["patchResource"], true);
+    }
+}
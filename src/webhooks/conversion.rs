@@ -0,0 +1,132 @@
+use kube::{
+    Resource,
+    core::{
+        Status,
+        conversion::{ConversionRequest, ConversionResponse, ConversionReview},
+    },
+};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::api::fleet_addon_config::{self as v1alpha1, v1alpha2};
+
+pub type ConversionResult<T> = std::result::Result<T, ConversionError>;
+
+#[derive(Error, Debug)]
+pub enum ConversionError {
+    #[error("Unsupported conversion from `{0}` to `{1}`")]
+    Unsupported(String, String),
+
+    #[error("Object conversion error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Handles a `ConversionReview` for the `FleetAddonConfig` resource.
+#[must_use]
+pub fn review(review: ConversionReview) -> ConversionReview {
+    let request = match ConversionRequest::try_from(review) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid conversion request: {e}");
+            return ConversionResponse::invalid(Status::failure(&e.to_string(), "InvalidRequest"))
+                .into_review();
+        }
+    };
+
+    let desired_api_version = request.desired_api_version.clone();
+    let objects = request.objects.clone();
+    let response = ConversionResponse::for_request(request);
+
+    match objects
+        .into_iter()
+        .map(|object| convert(object, &desired_api_version))
+        .collect::<ConversionResult<Vec<_>>>()
+    {
+        Ok(converted) => response.success(converted).into_review(),
+        Err(e) => {
+            warn!("Conversion to {desired_api_version} failed: {e}");
+            response
+                .failure(Status::failure(&e.to_string(), "ConversionFailed"))
+                .into_review()
+        }
+    }
+}
+
+/// Converts a single `FleetAddonConfig` object to the desired API version.
+///
+/// # Errors
+///
+/// This function will return an error if the version pair is not supported,
+/// or the object does not match the schema of its API version.
+pub fn convert(object: Value, desired_api_version: &str) -> ConversionResult<Value> {
+    let v1alpha1_version = v1alpha1::FleetAddonConfig::api_version(&());
+    let v1alpha2_version = v1alpha2::FleetAddonConfig::api_version(&());
+    let api_version = object
+        .get("apiVersion")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    debug!("Converting FleetAddonConfig from {api_version} to {desired_api_version}");
+
+    match (api_version.as_str(), desired_api_version) {
+        (from, to) if from == to => Ok(object),
+        (from, to) if from == v1alpha1_version && to == v1alpha2_version => {
+            let config: v1alpha1::FleetAddonConfig = serde_json::from_value(object)?;
+            Ok(serde_json::to_value(v1alpha2::FleetAddonConfig::from(
+                config,
+            ))?)
+        }
+        (from, to) if from == v1alpha2_version && to == v1alpha1_version => {
+            let config: v1alpha2::FleetAddonConfig = serde_json::from_value(object)?;
+            Ok(serde_json::to_value(v1alpha1::FleetAddonConfig::from(
+                config,
+            ))?)
+        }
+        (from, to) => Err(ConversionError::Unsupported(from.into(), to.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::convert;
+
+    #[test]
+    fn test_convert() {
+        let object = json!({
+            "apiVersion": "addons.cluster.x-k8s.io/v1alpha1",
+            "kind": "FleetAddonConfig",
+            "metadata": {"name": "fleet-addon-config"},
+            "spec": {
+                "cluster": {
+                    "hostNetwork": true,
+                    "namespaceSelector": {},
+                    "selector": {"matchLabels": {"import": "true"}},
+                },
+                "install": {"followLatest": true},
+            },
+        });
+
+        let converted = convert(object.clone(), "addons.cluster.x-k8s.io/v1alpha2").unwrap();
+        assert_eq!(converted["apiVersion"], "addons.cluster.x-k8s.io/v1alpha2");
+        assert_eq!(converted["spec"]["cluster"]["agent"]["hostNetwork"], true);
+        assert_eq!(
+            converted["spec"]["cluster"]["selectors"]["selector"]["matchLabels"]["import"],
+            "true"
+        );
+        assert_eq!(converted["spec"]["install"]["followLatest"], true);
+
+        let reverted = convert(converted, "addons.cluster.x-k8s.io/v1alpha1").unwrap();
+        assert_eq!(reverted["apiVersion"], "addons.cluster.x-k8s.io/v1alpha1");
+        assert_eq!(reverted["spec"]["install"], object["spec"]["install"]);
+        assert_eq!(
+            reverted["spec"]["cluster"]["selector"],
+            object["spec"]["cluster"]["selector"]
+        );
+
+        assert!(convert(object, "addons.cluster.x-k8s.io/v1beta1").is_err());
+    }
+}
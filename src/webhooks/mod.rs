@@ -0,0 +1,40 @@
+use std::{fs::File, io, io::BufReader, path::Path};
+
+use thiserror::Error;
+
+pub mod admission;
+pub mod conversion;
+
+pub type TlsConfigResult<T> = std::result::Result<T, TlsConfigError>;
+
+#[derive(Error, Debug)]
+pub enum TlsConfigError {
+    #[error("Certificate read error: {0}")]
+    Read(#[from] io::Error),
+
+    #[error("No private key found in {0}")]
+    MissingKey(String),
+
+    #[error("TLS config error: {0}")]
+    Config(#[from] rustls::Error),
+}
+
+/// Builds the TLS server config for webhooks from `tls.crt` and `tls.key`
+/// stored in the certificate directory.
+///
+/// # Errors
+///
+/// This function will return an error if the certificate or key can't be read or parsed.
+pub fn tls_config(cert_dir: &Path) -> TlsConfigResult<rustls::ServerConfig> {
+    let cert_file = &mut BufReader::new(File::open(cert_dir.join("tls.crt"))?);
+    let key_path = cert_dir.join("tls.key");
+    let key_file = &mut BufReader::new(File::open(&key_path)?);
+
+    let certs = rustls_pemfile::certs(cert_file).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(key_file)?
+        .ok_or_else(|| TlsConfigError::MissingKey(key_path.display().to_string()))?;
+
+    Ok(rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?)
+}